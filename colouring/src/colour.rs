@@ -0,0 +1,61 @@
+//! Colours of the rendered images.
+
+/// An 8-bit sRGB colour with alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rgba {
+    /// Red component.
+    pub r: u8,
+    /// Green component.
+    pub g: u8,
+    /// Blue component.
+    pub b: u8,
+    /// Opacity, `255` being opaque.
+    pub a: u8,
+}
+
+impl Rgba {
+    /// Opaque black.
+    pub const BLACK: Rgba = Rgba::opaque(0, 0, 0);
+    /// Opaque white.
+    pub const WHITE: Rgba = Rgba::opaque(255, 255, 255);
+
+    /// Creates the colour with the given components.
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Rgba { r, g, b, a }
+    }
+
+    /// Creates the opaque colour with the given components.
+    pub const fn opaque(r: u8, g: u8, b: u8) -> Self {
+        Rgba { r, g, b, a: 255 }
+    }
+
+    /// Creates the opaque colour of the given hue, saturation and value. The hue is in
+    /// turns, wrapping around `[0, 1)`; saturation and value are clamped to `[0, 1]`.
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Self {
+        let (saturation, value) = (saturation.clamp(0.0, 1.0), value.clamp(0.0, 1.0));
+        let sector = hue.rem_euclid(1.0) * 6.0;
+        let chroma = value * saturation;
+        let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+        let (r, g, b) = match sector as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let m = value - chroma;
+        Rgba::from_unit(r + m, g + m, b + m)
+    }
+
+    /// Creates the opaque colour with components given in `[0, 1]`, clamped.
+    pub fn from_unit(r: f64, g: f64, b: f64) -> Self {
+        let channel = |value: f64| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Rgba::opaque(channel(r), channel(g), channel(b))
+    }
+
+    /// Returns the components in the order `[r, g, b, a]`, the layout of RGBA image buffers.
+    pub fn to_array(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+}
@@ -0,0 +1,305 @@
+//! Multi-stop colour gradients, mapping the intensities of the fractals to colours.
+//!
+//! A pixel value `v`, the normalized iteration count of a [`PixelIntensity`] or its `zn`
+//! depending on the [`PixelChannel`] of the gradient, is first mapped to the gradient
+//! position `p = (v · scale + offset) · repeat`, then wrapped around the gradient or
+//! clamped to its ends, depending on its [`GradientMode`].
+
+use std::str::FromStr;
+
+use common::{PixelIntensity, Resolution};
+
+use crate::colour::Rgba;
+use crate::colour_space::ColourSpace;
+
+/// What a gradient does with the positions outside of `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GradientMode {
+    /// Wraps them around: the gradient repeats endlessly, blending its last stop back into
+    /// its first one.
+    #[default]
+    Cyclic,
+    /// Gives them the colour of the nearest end.
+    Clamped,
+}
+
+/// Channel of a [`PixelIntensity`] a gradient colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PixelChannel {
+    /// The normalized iteration count.
+    #[default]
+    Count,
+    /// The value chosen by the fractal and its options: the last value of the orbit, a
+    /// distance estimate, an orbit trap distance or average, a root, ...
+    Zn,
+}
+
+impl PixelChannel {
+    /// Every channel.
+    pub const ALL: [PixelChannel; 2] = [PixelChannel::Count, PixelChannel::Zn];
+
+    /// Returns the value of this channel in `pixel`.
+    pub fn value(self, pixel: &PixelIntensity) -> f64 {
+        match self {
+            PixelChannel::Count => pixel.count as f64,
+            PixelChannel::Zn => pixel.zn as f64,
+        }
+    }
+}
+
+impl FromStr for PixelChannel {
+    type Err = String;
+
+    /// Parses `count` or `zn`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "count" => Ok(PixelChannel::Count),
+            "zn" => Ok(PixelChannel::Zn),
+            _ => Err(format!("unknown pixel channel {name:?}, expected count or zn")),
+        }
+    }
+}
+
+/// A colour at a position of a gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColourStop {
+    /// Position of the stop, in `[0, 1]`.
+    pub position: f64,
+    /// Colour at the stop.
+    pub colour: Rgba,
+}
+
+impl ColourStop {
+    /// Creates the stop of `colour` at `position`.
+    pub fn new(position: f64, colour: Rgba) -> Self {
+        ColourStop { position, colour }
+    }
+}
+
+/// A gradient through colour stops, with the mapping of pixel values onto it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<ColourStop>,
+    space: ColourSpace,
+    mode: GradientMode,
+    channel: PixelChannel,
+    offset: f64,
+    scale: f64,
+    repeat: f64,
+    interior: Option<Rgba>,
+}
+
+impl Gradient {
+    /// Creates the cyclic gradient through `stops`, interpolated in Oklab, mapping the
+    /// normalized counts of the pixels to positions unchanged. The stops may come in any
+    /// order.
+    ///
+    /// # Errors
+    /// Returns an error if there is no stop or a position is outside of `[0, 1]`.
+    pub fn new(mut stops: Vec<ColourStop>) -> Result<Self, String> {
+        if stops.is_empty() {
+            return Err("gradient without colour stops".to_string());
+        }
+        if let Some(stop) = stops.iter().find(|stop| !(0.0..=1.0).contains(&stop.position)) {
+            return Err(format!("colour stop at {} outside of [0, 1]", stop.position));
+        }
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Ok(Gradient {
+            stops,
+            space: ColourSpace::default(),
+            mode: GradientMode::default(),
+            channel: PixelChannel::default(),
+            offset: 0.0,
+            scale: 1.0,
+            repeat: 1.0,
+            interior: None,
+        })
+    }
+
+    /// Creates the gradient through `colours`, evenly spaced from `0` to `1`.
+    ///
+    /// # Errors
+    /// Returns an error if there is no colour.
+    pub fn evenly_spaced(colours: &[Rgba]) -> Result<Self, String> {
+        let last = colours.len().saturating_sub(1).max(1) as f64;
+        let stops = colours
+            .iter()
+            .enumerate()
+            .map(|(index, colour)| ColourStop::new(index as f64 / last, *colour))
+            .collect();
+        Gradient::new(stops)
+    }
+
+    /// Interpolates between the stops in `space`.
+    pub fn with_space(mut self, space: ColourSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Wraps or clamps the positions outside of `[0, 1]`.
+    pub fn with_mode(mut self, mode: GradientMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Colours the `channel` of the pixels.
+    pub fn with_channel(mut self, channel: PixelChannel) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Shifts the gradient by `offset` along the pixel values, after scaling them.
+    pub fn with_offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Multiplies the pixel values by `scale`, to spread a narrow range of them over the
+    /// gradient.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Lays the gradient `repeat` times over the pixel values from `0` to `1`. Only whole
+    /// numbers of repetitions join up in clamped mode.
+    pub fn with_repeat(mut self, repeat: f64) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Gives `colour` to the points that never escaped, whose normalized count is `1`,
+    /// rather than a colour of the gradient.
+    pub fn with_interior(mut self, colour: Option<Rgba>) -> Self {
+        self.interior = colour;
+        self
+    }
+
+    /// Returns the stops of the gradient, by increasing position.
+    pub fn stops(&self) -> &[ColourStop] {
+        &self.stops
+    }
+
+    /// Returns the colour of the gradient at `position`, wrapped or clamped into `[0, 1]`.
+    ///
+    /// ```
+    /// use colouring::{Gradient, GradientMode, Rgba};
+    ///
+    /// let red = Rgba::opaque(255, 0, 0);
+    /// let cyclic = Gradient::evenly_spaced(&[Rgba::BLACK, red, Rgba::WHITE]).unwrap();
+    /// for position in [0.1, 0.45, 0.8, 0.99] {
+    ///     assert_eq!(cyclic.sample(position - 1.0), cyclic.sample(position));
+    ///     assert_eq!(cyclic.sample(position + 3.0), cyclic.sample(position));
+    /// }
+    /// // The last stop blends back into the first one.
+    /// assert_eq!(cyclic.sample(1.0), Rgba::BLACK);
+    ///
+    /// let clamped = cyclic.with_mode(GradientMode::Clamped);
+    /// assert_eq!(clamped.sample(-0.5), Rgba::BLACK);
+    /// assert_eq!(clamped.sample(1.0), Rgba::WHITE);
+    /// assert_eq!(clamped.sample(7.0), Rgba::WHITE);
+    /// assert_eq!(clamped.sample(0.5), red);
+    /// ```
+    pub fn sample(&self, position: f64) -> Rgba {
+        let position = match self.mode {
+            GradientMode::Cyclic => position.rem_euclid(1.0),
+            GradientMode::Clamped => position.clamp(0.0, 1.0),
+        };
+        let position = if position.is_nan() { 0.0 } else { position };
+
+        let next = self.stops.partition_point(|stop| stop.position <= position);
+        let (first, last) = (self.stops[0], self.stops[self.stops.len() - 1]);
+        let (from, to) = match (next, self.mode) {
+            (0, GradientMode::Clamped) => return first.colour,
+            (0, GradientMode::Cyclic) => (ColourStop::new(last.position - 1.0, last.colour), first),
+            (next, GradientMode::Clamped) if next == self.stops.len() => return last.colour,
+            (next, GradientMode::Cyclic) if next == self.stops.len() => {
+                (last, ColourStop::new(first.position + 1.0, first.colour))
+            }
+            (next, _) => (self.stops[next - 1], self.stops[next]),
+        };
+
+        let width = to.position - from.position;
+        if width <= 0.0 {
+            return from.colour;
+        }
+        self.space.mix(from.colour, to.colour, (position - from.position) / width)
+    }
+
+    /// Returns the colour of a pixel value, such as a normalized iteration count.
+    pub fn colour_value(&self, value: f64) -> Rgba {
+        let position = (value * self.scale + self.offset) * self.repeat;
+        if self.mode == GradientMode::Clamped && position > 1.0 && position <= self.repeat.max(1.0) {
+            // Repetitions end on the last stop rather than wrap to the first one.
+            let wrapped = position.rem_euclid(1.0);
+            return self.sample(if wrapped == 0.0 { 1.0 } else { wrapped });
+        }
+        self.sample(position)
+    }
+
+    /// Returns the colour of a pixel sent by a fractal, from the channel of the gradient.
+    /// Interior points are told by their normalized count whatever the channel.
+    ///
+    /// ```
+    /// use colouring::{Gradient, GradientMode, PixelChannel, Rgba};
+    /// use common::PixelIntensity;
+    ///
+    /// let gradient = Gradient::evenly_spaced(&[Rgba::BLACK, Rgba::WHITE])
+    ///     .unwrap()
+    ///     .with_mode(GradientMode::Clamped)
+    ///     .with_interior(Some(Rgba::opaque(255, 0, 0)));
+    /// let pixel = PixelIntensity::new(1.0, 0.0);
+    /// assert_eq!(gradient.colour_intensity(&pixel), Rgba::BLACK);
+    /// let by_zn = gradient.with_channel(PixelChannel::Zn);
+    /// assert_eq!(by_zn.colour_intensity(&pixel), Rgba::WHITE);
+    /// assert_eq!(by_zn.colour_intensity(&PixelIntensity::new(0.0, 1.0)), Rgba::opaque(255, 0, 0));
+    /// ```
+    pub fn colour_intensity(&self, pixel: &PixelIntensity) -> Rgba {
+        match self.interior {
+            Some(interior) if pixel.count >= 1.0 => interior,
+            _ => self.colour_value(self.channel.value(pixel)),
+        }
+    }
+
+    /// Colours the pixels of a fragment of the given resolution, sent row by row.
+    ///
+    /// # Errors
+    /// Returns an error if there are not `nx × ny` pixels.
+    pub fn colour_image(&self, pixels: &[PixelIntensity], resolution: Resolution) -> Result<RgbaImage, String> {
+        let (width, height) = (resolution.nx as u32, resolution.ny as u32);
+        if pixels.len() != width as usize * height as usize {
+            return Err(format!(
+                "image of {width}x{height} pixels given {} pixels",
+                pixels.len()
+            ));
+        }
+        let pixels = pixels.iter().map(|pixel| self.colour_intensity(pixel)).collect();
+        Ok(RgbaImage { width, height, pixels })
+    }
+}
+
+/// A coloured image, its pixels in the order the fractals send them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    /// Number of pixels of a row.
+    pub width: u32,
+    /// Number of rows.
+    pub height: u32,
+    /// The `width × height` pixels, row by row.
+    pub pixels: Vec<Rgba>,
+}
+
+impl RgbaImage {
+    /// Returns the colour of the pixel at column `x` and row `y`, `None` outside of the image.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Rgba> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.pixels.get(y as usize * self.width as usize + x as usize).copied()
+    }
+
+    /// Returns the pixels as interleaved RGBA bytes, row by row.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|pixel| pixel.to_array()).collect()
+    }
+}
@@ -0,0 +1,11 @@
+mod colour;
+mod colour_space;
+mod gradient;
+mod newton_basins;
+mod palettes;
+
+pub use colour::Rgba;
+pub use colour_space::ColourSpace;
+pub use gradient::{ColourStop, Gradient, GradientMode, PixelChannel, RgbaImage};
+pub use newton_basins::NewtonColouring;
+pub use palettes::Palette;
@@ -0,0 +1,112 @@
+//! Colouring of the basins of attraction of the Newton fractals.
+//!
+//! Each root gets its own hue, spread around the colour wheel by the golden ratio: the
+//! hues of consecutive roots are as far apart as possible whatever their number, so the
+//! Newton fractals with an unbounded number of roots need no palette size. The
+//! brightness of a point then falls with the number of steps it took to converge, which
+//! is continuous when the fractal computed the smooth count of
+//! `fraktals::smooth_convergence_count`.
+
+use common::{PixelIntensity, PixelRecord};
+
+use crate::colour::Rgba;
+
+/// Fractional part of the golden ratio, the hue step between consecutive roots, in turns.
+const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_894_9;
+
+/// Colouring of Newton fractals by root and convergence speed.
+#[derive(Debug, Clone, Copy)]
+pub struct NewtonColouring {
+    /// Hue of the root `0`, in turns.
+    pub hue_offset: f64,
+    /// Saturation of the basin colours, in `[0, 1]`.
+    pub saturation: f64,
+    /// Factor applied to the brightness for each step a point takes to converge, in `(0, 1]`.
+    pub falloff: f64,
+    /// Lowest brightness of the converged points, in `[0, 1]`, so that slow basins keep
+    /// their hue.
+    pub min_value: f64,
+    /// Colour of the points that did not converge to a root.
+    pub non_converged: Rgba,
+}
+
+impl Default for NewtonColouring {
+    fn default() -> Self {
+        NewtonColouring {
+            hue_offset: 0.0,
+            saturation: 0.75,
+            falloff: 0.9,
+            min_value: 0.15,
+            non_converged: Rgba::BLACK,
+        }
+    }
+}
+
+impl NewtonColouring {
+    /// Returns the hue of `root`, in turns. Consecutive roots are always more than a third
+    /// of a turn apart, and get distinct colours:
+    ///
+    /// ```
+    /// use colouring::NewtonColouring;
+    ///
+    /// let colouring = NewtonColouring::default();
+    /// for root in 0..1000 {
+    ///     let gap = (colouring.hue(root + 1) - colouring.hue(root)).rem_euclid(1.0);
+    ///     assert!(gap.min(1.0 - gap) > 1.0 / 3.0, "roots {root} and {} are {gap} turns apart", root + 1);
+    ///     assert_ne!(colouring.colour(Some(root), 2.0), colouring.colour(Some(root + 1), 2.0));
+    /// }
+    /// ```
+    pub fn hue(&self, root: u32) -> f64 {
+        (self.hue_offset + root as f64 * GOLDEN_RATIO_CONJUGATE).rem_euclid(1.0)
+    }
+
+    /// Returns the colour of a point that converged to `root` in `steps` steps, possibly
+    /// fractional, or the colour of non-converged points for `None`.
+    pub fn colour(&self, root: Option<u32>, steps: f64) -> Rgba {
+        let Some(root) = root else {
+            return self.non_converged;
+        };
+        let min_value = self.min_value.clamp(0.0, 1.0);
+        let value = min_value + (1.0 - min_value) * self.falloff.powf(steps.max(0.0));
+        Rgba::from_hsv(self.hue(root), self.saturation, value)
+    }
+
+    /// Returns the colour of a pixel sent by a Newton fractal: `zn` holds the root, negative
+    /// for non-converged points, and `count` the steps normalized by `max_iteration`.
+    /// Only the root decides convergence, as counts close to `max_iteration` round to 1:
+    ///
+    /// ```
+    /// use colouring::{NewtonColouring, Rgba};
+    /// use common::PixelIntensity;
+    ///
+    /// let colouring = NewtonColouring { non_converged: Rgba::WHITE, ..NewtonColouring::default() };
+    /// assert_eq!(colouring.colour_intensity(&PixelIntensity::new(-1.0, 0.1), 100), Rgba::WHITE);
+    /// assert_eq!(colouring.colour_intensity(&PixelIntensity::new(2.0, 0.1), 100), colouring.colour(Some(2), 10.0));
+    /// assert_eq!(colouring.colour_intensity(&PixelIntensity::new(2.0, 1.0), 100), colouring.colour(Some(2), 100.0));
+    ///
+    /// // 99_999_999 steps out of 100_000_000 round to a normalized count of 1.0.
+    /// let pixel = PixelIntensity::new(1.0, (99_999_999.0 / 100_000_000.0) as f32);
+    /// assert_eq!(pixel.count, 1.0);
+    /// assert_ne!(colouring.colour_intensity(&pixel, 100_000_000), Rgba::WHITE);
+    /// ```
+    pub fn colour_intensity(&self, pixel: &PixelIntensity, max_iteration: u32) -> Rgba {
+        let steps = pixel.count as f64 * max_iteration as f64;
+        let converged = pixel.zn >= 0.0;
+        self.colour(converged.then_some(pixel.zn as u32), steps)
+    }
+
+    /// Returns the colour of the record of a pixel of a Newton fractal, non-converged when
+    /// it has no root.
+    pub fn colour_record(&self, record: &PixelRecord) -> Rgba {
+        let steps = record.smooth_count.unwrap_or(record.count as f64);
+        self.colour(record.root, steps)
+    }
+
+    /// Colours the pixels sent by a Newton fractal, in order.
+    pub fn colour_pixels(&self, pixels: &[PixelIntensity], max_iteration: u32) -> Vec<Rgba> {
+        pixels
+            .iter()
+            .map(|pixel| self.colour_intensity(pixel, max_iteration))
+            .collect()
+    }
+}
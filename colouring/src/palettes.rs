@@ -0,0 +1,147 @@
+//! Built-in palettes, selectable by name.
+
+use std::str::FromStr;
+
+use crate::colour::Rgba;
+use crate::colour_space::ColourSpace;
+use crate::gradient::{ColourStop, Gradient, GradientMode};
+
+/// A named built-in gradient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    /// Black to white, clamped.
+    Greyscale,
+    /// The deep blue, white and gold cycle of Ultra Fractal's default gradient.
+    #[default]
+    Classic,
+    /// Black through red and orange to pale yellow, clamped.
+    Fire,
+    /// Dark navy through blue and teal to foam white, cyclic.
+    Ocean,
+    /// Every hue around the colour wheel, cyclic.
+    Rainbow,
+    /// Dark purple through teal to yellow, approximating viridis: readable by colour-blind
+    /// viewers and in greyscale print, clamped.
+    Viridis,
+    /// Light grey through blue, dark purple and red back to light grey, cyclic.
+    Twilight,
+}
+
+impl Palette {
+    /// Every built-in palette.
+    pub const ALL: [Palette; 7] = [
+        Palette::Greyscale,
+        Palette::Classic,
+        Palette::Fire,
+        Palette::Ocean,
+        Palette::Rainbow,
+        Palette::Viridis,
+        Palette::Twilight,
+    ];
+
+    /// Returns the name of the palette, as parsed back by [`Palette::from_str`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Greyscale => "greyscale",
+            Palette::Classic => "classic",
+            Palette::Fire => "fire",
+            Palette::Ocean => "ocean",
+            Palette::Rainbow => "rainbow",
+            Palette::Viridis => "viridis",
+            Palette::Twilight => "twilight",
+        }
+    }
+
+    /// Returns the gradient of the palette, to be adjusted with the builder methods of
+    /// [`Gradient`].
+    pub fn gradient(self) -> Gradient {
+        let (stops, space, mode): (&[(f64, Rgba)], _, _) = match self {
+            Palette::Greyscale => (
+                &[(0.0, Rgba::BLACK), (1.0, Rgba::WHITE)],
+                ColourSpace::Oklab,
+                GradientMode::Clamped,
+            ),
+            Palette::Classic => (
+                &[
+                    (0.0, Rgba::opaque(0, 7, 100)),
+                    (0.16, Rgba::opaque(32, 107, 203)),
+                    (0.42, Rgba::opaque(237, 255, 255)),
+                    (0.6425, Rgba::opaque(255, 170, 0)),
+                    (0.8575, Rgba::opaque(0, 2, 0)),
+                ],
+                ColourSpace::Srgb,
+                GradientMode::Cyclic,
+            ),
+            Palette::Fire => (
+                &[
+                    (0.0, Rgba::BLACK),
+                    (0.3, Rgba::opaque(160, 16, 0)),
+                    (0.6, Rgba::opaque(255, 128, 0)),
+                    (1.0, Rgba::opaque(255, 250, 200)),
+                ],
+                ColourSpace::LinearRgb,
+                GradientMode::Clamped,
+            ),
+            Palette::Ocean => (
+                &[
+                    (0.0, Rgba::opaque(2, 8, 40)),
+                    (0.35, Rgba::opaque(10, 70, 160)),
+                    (0.7, Rgba::opaque(40, 180, 190)),
+                    (0.85, Rgba::opaque(235, 250, 250)),
+                ],
+                ColourSpace::Oklab,
+                GradientMode::Cyclic,
+            ),
+            Palette::Rainbow => (
+                &[
+                    (0.0, Rgba::opaque(255, 0, 0)),
+                    (1.0 / 3.0, Rgba::opaque(0, 255, 0)),
+                    (2.0 / 3.0, Rgba::opaque(0, 0, 255)),
+                ],
+                ColourSpace::Hsv,
+                GradientMode::Cyclic,
+            ),
+            Palette::Viridis => (
+                &[
+                    (0.0, Rgba::opaque(68, 1, 84)),
+                    (0.25, Rgba::opaque(59, 82, 139)),
+                    (0.5, Rgba::opaque(33, 145, 140)),
+                    (0.75, Rgba::opaque(94, 201, 98)),
+                    (1.0, Rgba::opaque(253, 231, 37)),
+                ],
+                ColourSpace::Oklab,
+                GradientMode::Clamped,
+            ),
+            Palette::Twilight => (
+                &[
+                    (0.0, Rgba::opaque(226, 217, 226)),
+                    (0.25, Rgba::opaque(94, 128, 185)),
+                    (0.5, Rgba::opaque(47, 20, 67)),
+                    (0.75, Rgba::opaque(177, 87, 76)),
+                ],
+                ColourSpace::Oklab,
+                GradientMode::Cyclic,
+            ),
+        };
+        let stops = stops.iter().map(|(position, colour)| ColourStop::new(*position, *colour)).collect();
+        Gradient::new(stops)
+            .expect("built-in palettes have valid stops")
+            .with_space(space)
+            .with_mode(mode)
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Parses the name of a built-in palette, as returned by [`Palette::name`].
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Palette::ALL
+            .into_iter()
+            .find(|palette| palette.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Palette::ALL.iter().map(|palette| palette.name()).collect();
+                format!("unknown palette {name:?}, expected one of {}", names.join(", "))
+            })
+    }
+}
@@ -0,0 +1,259 @@
+//! Arbitrary-precision complex numbers built on [`BigFloat`].
+
+use crate::big_float::BigFloat;
+use crate::complex::{Complex, ComplexTrait};
+
+/// Extra bits carried by the transcendental functions to absorb rounding and cancellation.
+const GUARD_BITS: u32 = 64;
+
+/// Largest position of the most significant bit of the real part of an exponential
+/// argument: from `2^61` on, `e^z` saturates or flushes to zero in [`BigFloat`].
+const MAX_REAL_TOP_BIT: i64 = 61;
+
+/// Largest position of the most significant bit of the imaginary part of an exponential
+/// argument whose angle is computed. Each bit costs a squaring at a precision raised by
+/// one bit, so larger arguments give the overflow value instead.
+const MAX_ANGLE_TOP_BIT: i64 = 4096;
+
+/// A complex number with arbitrary-precision real and imaginary parts.
+///
+/// The exponential, sine and cosine accept any argument, saturating when the result leaves
+/// the range of [`BigFloat`]:
+///
+/// ```
+/// use common::{BigComplex, ComplexTrait};
+///
+/// let z = |re: &str, im: &str| BigComplex::parse(re, im, 64).unwrap();
+/// let angle = 2f64.powi(40);
+/// let unit = z("0", "1099511627776").exponential().to_complex();
+/// assert!((unit.re - angle.cos()).abs() < 1e-12 && (unit.im - angle.sin()).abs() < 1e-12);
+/// assert!((z("3e9", "0").sine().to_complex().re - 3e9f64.sin()).abs() < 1e-12);
+/// assert!((z("3e9", "0").cosine().to_complex().re - 3e9f64.cos()).abs() < 1e-12);
+///
+/// assert_eq!(z("1e12", "0").exponential().to_complex().re, f64::INFINITY);
+/// assert_eq!(z("1e30", "0").exponential().to_complex().re, f64::INFINITY);
+/// assert_eq!(z("-1e30", "1").exponential().to_complex().re, 0.0);
+/// let sine = z("0", "1e30").sine().to_complex();
+/// assert!(sine.re == 0.0 && sine.im == f64::INFINITY);
+/// assert!(z("0", "1e2000").exponential().to_complex().re.is_infinite());
+/// ```
+#[derive(Debug, Clone)]
+pub struct BigComplex {
+    /// Real part of the complex number.
+    pub re: BigFloat,
+    /// Imaginary part of the complex number.
+    pub im: BigFloat,
+}
+
+impl BigComplex {
+    /// Parses both parts from decimal strings at the given precision in bits.
+    ///
+    /// # Errors
+    /// Returns an error if a part is not a valid decimal number.
+    pub fn parse(re: &str, im: &str, precision: u32) -> Result<Self, String> {
+        Ok(BigComplex {
+            re: BigFloat::parse(re, precision)?,
+            im: BigFloat::parse(im, precision)?,
+        })
+    }
+
+    /// Returns zero at the given precision.
+    pub fn zero(precision: u32) -> Self {
+        BigComplex {
+            re: BigFloat::zero(precision),
+            im: BigFloat::zero(precision),
+        }
+    }
+
+    /// Precision of the widest part, in bits.
+    pub fn precision(&self) -> u32 {
+        self.re.precision().max(self.im.precision())
+    }
+
+    /// Returns this value with both parts rounded to a new precision.
+    pub fn with_precision(&self, precision: u32) -> Self {
+        BigComplex {
+            re: self.re.with_precision(precision),
+            im: self.im.with_precision(precision),
+        }
+    }
+
+    /// Returns the nearest `f64` complex number.
+    pub fn to_complex(&self) -> Complex {
+        Complex::new(self.re.to_f64(), self.im.to_f64())
+    }
+
+    /// Returns the value multiplied by `2^power`, which is exact.
+    fn mul_pow2(&self, power: i64) -> Self {
+        BigComplex {
+            re: self.re.mul_pow2(power),
+            im: self.im.mul_pow2(power),
+        }
+    }
+
+    /// Returns the opposite value.
+    fn negate_parts(&self) -> Self {
+        BigComplex {
+            re: self.re.negate(),
+            im: self.im.negate(),
+        }
+    }
+
+    /// Returns `i` times the value.
+    fn times_i(&self) -> Self {
+        BigComplex {
+            re: self.im.negate(),
+            im: self.re.clone(),
+        }
+    }
+
+    /// Position of the most significant bit of the larger part, `None` for zero.
+    fn top_bit(&self) -> Option<i64> {
+        [&self.re, &self.im]
+            .into_iter()
+            .filter(|part| !part.is_zero())
+            .map(BigFloat::top_bit)
+            .max()
+    }
+
+    /// Returns the value whose parts are both saturated, given for exponentials out of
+    /// reach. It converts to infinite `f64` parts.
+    fn overflow(precision: u32) -> Self {
+        BigComplex {
+            re: BigFloat::saturated(false, precision),
+            im: BigFloat::saturated(false, precision),
+        }
+    }
+
+    /// Computes `e^z` at `precision` bits: the argument is halved until it is small,
+    /// summed as a Taylor series, then squared back.
+    ///
+    /// Past `2^61`, a positive real part saturates the result and a negative one flushes
+    /// it to zero; past `2^4096`, the imaginary part gives [`BigComplex::overflow`].
+    fn exponential_at(&self, precision: u32) -> Self {
+        if !self.im.is_zero() && self.im.top_bit() > MAX_ANGLE_TOP_BIT {
+            return Self::overflow(precision);
+        }
+        if !self.re.is_zero() && self.re.top_bit() > MAX_REAL_TOP_BIT {
+            if self.re.is_negative() {
+                return Self::zero(precision);
+            }
+            let saturate = |part: BigFloat| {
+                if part.is_zero() {
+                    part
+                } else {
+                    BigFloat::saturated(part.is_negative(), precision)
+                }
+            };
+            let angle = BigComplex::new(BigFloat::zero(precision), self.im.clone()).exponential_at(precision);
+            return BigComplex::new(saturate(angle.re), saturate(angle.im));
+        }
+
+        let halvings = self.top_bit().map_or(0, |top| (top + 2).max(0));
+        let working = precision + GUARD_BITS + halvings as u32;
+        let z = self.with_precision(working).mul_pow2(-halvings);
+
+        let one = BigFloat::from_i64(1, working);
+        let mut sum = BigComplex::new(one, BigFloat::zero(working));
+        let mut term = sum.clone();
+        for k in 1.. {
+            term = term.multiply(&z);
+            let k = BigFloat::from_i64(k, working);
+            term = BigComplex {
+                re: term.re.divide(&k),
+                im: term.im.divide(&k),
+            };
+            sum = sum.add(&term);
+            match (term.top_bit(), sum.top_bit()) {
+                (Some(term_top), Some(sum_top)) if term_top > sum_top - working as i64 => continue,
+                _ => break,
+            }
+        }
+
+        for _ in 0..halvings {
+            sum = sum.square();
+        }
+        sum.with_precision(precision)
+    }
+}
+
+impl ComplexTrait for BigComplex {
+    type Real = BigFloat;
+
+    fn new(re: BigFloat, im: BigFloat) -> Self {
+        BigComplex { re, im }
+    }
+
+    fn add(&self, other: &BigComplex) -> BigComplex {
+        BigComplex {
+            re: self.re.add(&other.re),
+            im: self.im.add(&other.im),
+        }
+    }
+
+    fn argument(&self) -> f64 {
+        // Scale both parts together so that tiny or huge values keep their ratio in f64.
+        let Some(top) = self.top_bit() else {
+            return 0.0;
+        };
+        let scaled = self.mul_pow2(-top);
+        scaled.im.to_f64().atan2(scaled.re.to_f64())
+    }
+
+    fn cosine(&self) -> BigComplex {
+        let precision = self.precision();
+        let positive = self.times_i().exponential_at(precision + GUARD_BITS);
+        let negative = self.times_i().negate_parts().exponential_at(precision + GUARD_BITS);
+        positive.add(&negative).mul_pow2(-1).with_precision(precision)
+    }
+
+    fn divide(&self, other: BigComplex) -> BigComplex {
+        let divisor = other.square_norm();
+        BigComplex {
+            re: self.re.multiply(&other.re).add(&self.im.multiply(&other.im)).divide(&divisor),
+            im: self.im.multiply(&other.re).subtract(&self.re.multiply(&other.im)).divide(&divisor),
+        }
+    }
+
+    fn exponential(&self) -> BigComplex {
+        self.exponential_at(self.precision())
+    }
+
+    fn multiply(&self, other: &BigComplex) -> BigComplex {
+        BigComplex {
+            re: self.re.multiply(&other.re).subtract(&self.im.multiply(&other.im)),
+            im: self.re.multiply(&other.im).add(&self.im.multiply(&other.re)),
+        }
+    }
+
+    fn sine(&self) -> BigComplex {
+        // sin z = (e^{iz} - e^{-iz}) / 2i
+        let precision = self.precision();
+        let positive = self.times_i().exponential_at(precision + GUARD_BITS);
+        let negative = self.times_i().negate_parts().exponential_at(precision + GUARD_BITS);
+        let difference = positive.subtract(&negative).mul_pow2(-1);
+        BigComplex {
+            re: difference.im,
+            im: difference.re.negate(),
+        }
+        .with_precision(precision)
+    }
+
+    fn square(&self) -> BigComplex {
+        BigComplex {
+            re: self.re.multiply(&self.re).subtract(&self.im.multiply(&self.im)),
+            im: self.re.multiply(&self.im).mul_pow2(1),
+        }
+    }
+
+    fn square_norm(&self) -> BigFloat {
+        self.re.multiply(&self.re).add(&self.im.multiply(&self.im))
+    }
+
+    fn subtract(&self, other: &BigComplex) -> BigComplex {
+        BigComplex {
+            re: self.re.subtract(&other.re),
+            im: self.im.subtract(&other.im),
+        }
+    }
+}
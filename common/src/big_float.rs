@@ -0,0 +1,792 @@
+//! Arbitrary-precision binary floating-point numbers.
+//!
+//! A [`BigFloat`] stores a sign, an integer mantissa of exactly `precision` bits and a
+//! binary exponent, so that its value is `±mantissa × 2^exponent`. Every operation
+//! rounds its result to the nearest representable value at the largest precision of
+//! its operands, ties to even. Exponents are 64-bit, so values far beyond the range of
+//! `f64` (such as `1e-10000`) are representable. Results whose magnitude exceeds
+//! `2^(2^60)` saturate to the largest value, which converts to an infinite `f64`, and
+//! those below `2^(-2^60)` flush to zero.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Decimal orders of magnitude [`BigFloat::parse`] accepts beyond those of its digits and
+/// precision. Exponents are bounded so that a malformed string cannot make it build a
+/// power of ten of billions of bits.
+const PARSE_EXPONENT_ALLOWANCE: u64 = 10_000;
+
+/// Largest position of the most significant bit of a [`BigFloat`], far enough from the
+/// limits of `i64` that adding two exponents cannot overflow.
+const MAX_TOP_BIT: i64 = 1 << 60;
+
+/// Operations on natural numbers stored as little-endian `u32` limbs.
+mod natural {
+    use std::cmp::Ordering;
+
+    pub fn trim(a: &mut Vec<u32>) {
+        while a.last() == Some(&0) {
+            a.pop();
+        }
+    }
+
+    pub fn bits(a: &[u32]) -> u64 {
+        match a.iter().rposition(|limb| *limb != 0) {
+            Some(top) => top as u64 * 32 + (32 - a[top].leading_zeros()) as u64,
+            None => 0,
+        }
+    }
+
+    pub fn bit(a: &[u32], index: u64) -> bool {
+        let limb = (index / 32) as usize;
+        limb < a.len() && (a[limb] >> (index % 32)) & 1 == 1
+    }
+
+    /// Tells whether any bit strictly below `index` is set.
+    pub fn any_below(a: &[u32], index: u64) -> bool {
+        let limb = (index / 32) as usize;
+        let mask = (1u32 << (index % 32)) - 1;
+        a.iter().take(limb).any(|limb| *limb != 0) || a.get(limb).is_some_and(|limb| limb & mask != 0)
+    }
+
+    pub fn cmp(a: &[u32], b: &[u32]) -> Ordering {
+        let (a_bits, b_bits) = (bits(a), bits(b));
+        if a_bits != b_bits {
+            return a_bits.cmp(&b_bits);
+        }
+        let len = a_bits.div_ceil(32) as usize;
+        for i in (0..len).rev() {
+            match a[i].cmp(&b[i]) {
+                Ordering::Equal => continue,
+                other => return other,
+            }
+        }
+        Ordering::Equal
+    }
+
+    pub fn add(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..a.len().max(b.len()) {
+            let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+            result.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            result.push(carry as u32);
+        }
+        result
+    }
+
+    /// Computes `a - b`, assuming `a >= b`.
+    pub fn sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+        let mut result = Vec::with_capacity(a.len());
+        let mut borrow = 0i64;
+        for (i, limb) in a.iter().enumerate() {
+            let mut difference = *limb as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += 1 << 32;
+                borrow = 1;
+            }
+            result.push(difference as u32);
+        }
+        trim(&mut result);
+        result
+    }
+
+    pub fn mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+        if a.is_empty() || b.is_empty() {
+            return Vec::new();
+        }
+        let mut result = vec![0u32; a.len() + b.len()];
+        for (i, x) in a.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, y) in b.iter().enumerate() {
+                let product = *x as u64 * *y as u64 + result[i + j] as u64 + carry;
+                result[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            result[i + b.len()] = carry as u32;
+        }
+        trim(&mut result);
+        result
+    }
+
+    pub fn mul_small(a: &[u32], factor: u32) -> Vec<u32> {
+        mul(a, &[factor])
+    }
+
+    pub fn add_small(a: &[u32], term: u32) -> Vec<u32> {
+        add(a, &[term])
+    }
+
+    pub fn pow_small(base: u32, exponent: u32) -> Vec<u32> {
+        let mut result = vec![1];
+        let mut square = vec![base];
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = mul(&result, &square);
+            }
+            square = mul(&square, &square);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    pub fn shl(a: &[u32], shift: u64) -> Vec<u32> {
+        if a.is_empty() {
+            return Vec::new();
+        }
+        let limbs = (shift / 32) as usize;
+        let bits = (shift % 32) as u32;
+        let mut result = vec![0u32; limbs];
+        let mut carry = 0u32;
+        for limb in a {
+            if bits == 0 {
+                result.push(*limb);
+            } else {
+                result.push((limb << bits) | carry);
+                carry = limb >> (32 - bits);
+            }
+        }
+        if carry > 0 {
+            result.push(carry);
+        }
+        trim(&mut result);
+        result
+    }
+
+    pub fn shr(a: &[u32], shift: u64) -> Vec<u32> {
+        let limbs = (shift / 32) as usize;
+        if limbs >= a.len() {
+            return Vec::new();
+        }
+        let bits = (shift % 32) as u32;
+        let mut result: Vec<u32> = Vec::with_capacity(a.len() - limbs);
+        for i in limbs..a.len() {
+            if bits == 0 {
+                result.push(a[i]);
+            } else {
+                let high = a.get(i + 1).map_or(0, |next| next << (32 - bits));
+                result.push((a[i] >> bits) | high);
+            }
+        }
+        trim(&mut result);
+        result
+    }
+
+    /// Long division, returning the quotient and the remainder.
+    pub fn div_rem(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+        assert!(bits(b) > 0, "division by zero");
+        let mut quotient = vec![0u32; a.len()];
+        let mut remainder: Vec<u32> = Vec::new();
+        for index in (0..bits(a)).rev() {
+            remainder = shl(&remainder, 1);
+            if bit(a, index) {
+                if remainder.is_empty() {
+                    remainder.push(1);
+                } else {
+                    remainder[0] |= 1;
+                }
+            }
+            if cmp(&remainder, b) != Ordering::Less {
+                remainder = sub(&remainder, b);
+                quotient[(index / 32) as usize] |= 1 << (index % 32);
+            }
+        }
+        trim(&mut quotient);
+        (quotient, remainder)
+    }
+
+    /// Integer square root, rounded down.
+    pub fn sqrt(a: &[u32]) -> Vec<u32> {
+        if a.is_empty() {
+            return Vec::new();
+        }
+        let mut x = shl(&[1], bits(a).div_ceil(2));
+        loop {
+            let (quotient, _) = div_rem(a, &x);
+            let y = shr(&add(&x, &quotient), 1);
+            if cmp(&y, &x) != Ordering::Less {
+                return x;
+            }
+            x = y;
+        }
+    }
+}
+
+/// An arbitrary-precision binary floating-point number.
+///
+/// At 53 bits, operations round exactly like `f64` within its normal range, ties included,
+/// and conversions to `f64` round once, subnormals included:
+///
+/// ```
+/// use common::BigFloat;
+///
+/// let values = [1.0, -0.1, 3.0, 1e-300, 7.25e200, -123456.789, 9007199254740992.0];
+/// for a in values {
+///     for b in values {
+///         let (x, y) = (BigFloat::from_f64(a, 53), BigFloat::from_f64(b, 53));
+///         assert_eq!(x.add(&y).to_f64(), a + b);
+///         assert_eq!(x.multiply(&y).to_f64(), a * b);
+///         assert_eq!(x.divide(&y).to_f64(), a / b);
+///     }
+///     assert_eq!(BigFloat::from_f64(a.abs(), 53).sqrt().to_f64(), a.abs().sqrt());
+/// }
+///
+/// // 2^53 + 1 and 2^53 + 3 lie halfway between two 53-bit values, and round to the even one.
+/// let two_53 = 9007199254740992i64;
+/// assert_eq!(BigFloat::from_i64(two_53 + 1, 64).with_precision(53), BigFloat::from_i64(two_53, 53));
+/// assert_eq!(BigFloat::from_i64(two_53 + 3, 64).with_precision(53), BigFloat::from_i64(two_53 + 4, 53));
+/// assert_eq!(BigFloat::from_i64(two_53 + 1, 64).to_f64(), two_53 as f64);
+/// // Just above a tie by a bit past the 64th, which rounding to 64 bits first would lose.
+/// let above_tie = BigFloat::from_i64((1 << 62) + (1 << 9), 128).mul_pow2(7).add(&BigFloat::from_i64(1, 128));
+/// assert_eq!(above_tie.to_f64(), 2f64.powi(69) + 2f64.powi(17));
+/// // Halfway between 0 and the smallest subnormal, and just above.
+/// let half_subnormal = BigFloat::from_f64(f64::from_bits(1), 64).mul_pow2(-1);
+/// assert_eq!(half_subnormal.to_f64(), 0.0);
+/// assert_eq!(half_subnormal.add(&half_subnormal.mul_pow2(-40)).to_f64(), f64::from_bits(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BigFloat {
+    negative: bool,
+    /// Mantissa of exactly `precision` bits, empty for zero.
+    mantissa: Vec<u32>,
+    exponent: i64,
+    precision: u32,
+}
+
+impl BigFloat {
+    /// Returns zero with the given mantissa precision in bits.
+    pub fn zero(precision: u32) -> Self {
+        BigFloat {
+            negative: false,
+            mantissa: Vec::new(),
+            exponent: 0,
+            precision: precision.max(1),
+        }
+    }
+
+    /// Returns the value of the largest magnitude with `precision` bits, which every result
+    /// beyond the exponent range saturates to.
+    pub(crate) fn saturated(negative: bool, precision: u32) -> Self {
+        let precision = precision.max(1);
+        let ones = natural::sub(&natural::shl(&[1], precision as u64), &[1]);
+        BigFloat {
+            negative,
+            mantissa: ones,
+            exponent: MAX_TOP_BIT - precision as i64,
+            precision,
+        }
+    }
+
+    /// Rounds `±mantissa × 2^exponent` to the nearest value with `precision` bits, ties to
+    /// even, saturating outside of the exponent range.
+    fn rounded(negative: bool, mut mantissa: Vec<u32>, mut exponent: i64, precision: u32) -> Self {
+        natural::trim(&mut mantissa);
+        let precision = precision.max(1);
+        let bits = natural::bits(&mantissa);
+        if bits == 0 {
+            return Self::zero(precision);
+        }
+
+        if bits > precision as u64 {
+            let shift = bits - precision as u64;
+            let half = natural::bit(&mantissa, shift - 1);
+            let sticky = natural::any_below(&mantissa, shift - 1);
+            mantissa = natural::shr(&mantissa, shift);
+            exponent += shift as i64;
+            if half && (sticky || natural::bit(&mantissa, 0)) {
+                mantissa = natural::add_small(&mantissa, 1);
+                if natural::bits(&mantissa) > precision as u64 {
+                    mantissa = natural::shr(&mantissa, 1);
+                    exponent += 1;
+                }
+            }
+        } else if bits < precision as u64 {
+            let shift = precision as u64 - bits;
+            mantissa = natural::shl(&mantissa, shift);
+            exponent -= shift as i64;
+        }
+
+        let top = exponent + precision as i64;
+        if top > MAX_TOP_BIT {
+            return Self::saturated(negative, precision);
+        }
+        if top < -MAX_TOP_BIT {
+            return Self::zero(precision);
+        }
+
+        BigFloat {
+            negative,
+            mantissa,
+            exponent,
+            precision,
+        }
+    }
+
+    /// Converts an `f64` exactly, then rounds it to `precision` bits.
+    ///
+    /// # Panics
+    /// Panics if `value` is infinite or NaN.
+    pub fn from_f64(value: f64, precision: u32) -> Self {
+        assert!(value.is_finite(), "cannot convert {value} to BigFloat");
+        if value == 0.0 {
+            return Self::zero(precision);
+        }
+        let bits = value.abs().to_bits();
+        let biased_exponent = (bits >> 52) as i64;
+        let fraction = bits & ((1 << 52) - 1);
+        let (mantissa, exponent) = if biased_exponent == 0 {
+            (fraction, -1074)
+        } else {
+            (fraction | (1 << 52), biased_exponent - 1075)
+        };
+        Self::rounded(
+            value < 0.0,
+            vec![mantissa as u32, (mantissa >> 32) as u32],
+            exponent,
+            precision,
+        )
+    }
+
+    /// Converts an integer, rounding it to `precision` bits.
+    pub fn from_i64(value: i64, precision: u32) -> Self {
+        let magnitude = value.unsigned_abs();
+        Self::rounded(
+            value < 0,
+            vec![magnitude as u32, (magnitude >> 32) as u32],
+            0,
+            precision,
+        )
+    }
+
+    /// Parses a decimal string such as `-1.25`, `3e-120` or `0.000123E+4`, rounding it
+    /// to the nearest value with `precision` bits.
+    ///
+    /// The power of ten applied to the digits may not exceed `10^10000` times what the
+    /// precision and the number of digits given account for, about `10^(0.3 precision +
+    /// digits)`.
+    ///
+    /// ```
+    /// use common::BigFloat;
+    ///
+    /// for text in ["-1.25", "0.1", "3e-120", "0.000123E+4", "1.7976931348623157e308", "5e-324"] {
+    ///     let parsed = BigFloat::parse(text, 53).unwrap();
+    ///     assert_eq!(parsed.to_f64(), text.parse::<f64>().unwrap(), "{text}");
+    /// }
+    /// assert_eq!(BigFloat::parse("1e-10000", 64).unwrap().to_decimal_string(5), "1e-10000");
+    /// assert_eq!(BigFloat::parse("+2.5E+3", 64).unwrap().to_f64(), 2500.0);
+    ///
+    /// assert!(BigFloat::parse("1e4000000000", 128).is_err());
+    /// assert!(BigFloat::parse("1.2.3", 64).is_err());
+    /// assert!(BigFloat::parse("e5", 64).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the string is not a valid decimal number or its exponent is out
+    /// of range.
+    pub fn parse(text: &str, precision: u32) -> Result<Self, String> {
+        let text = text.trim();
+        let (negative, unsigned) = match text.as_bytes().first() {
+            Some(b'-') => (true, &text[1..]),
+            Some(b'+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (significand, decimal_exponent) = match unsigned.find(['e', 'E']) {
+            Some(index) => (
+                &unsigned[..index],
+                unsigned[index + 1..]
+                    .parse::<i64>()
+                    .map_err(|_| format!("invalid exponent in {text:?}"))?,
+            ),
+            None => (unsigned, 0),
+        };
+
+        let mut digits = Vec::new();
+        let mut digit_count = 0u64;
+        let mut fraction_digits = 0i64;
+        let mut seen_point = false;
+        let mut seen_digit = false;
+        for character in significand.chars() {
+            match character {
+                '0'..='9' => {
+                    digits = natural::add_small(&natural::mul_small(&digits, 10), character as u32 - '0' as u32);
+                    seen_digit = true;
+                    digit_count += 1;
+                    if seen_point {
+                        fraction_digits += 1;
+                    }
+                }
+                '.' if !seen_point => seen_point = true,
+                _ => return Err(format!("invalid character {character:?} in {text:?}")),
+            }
+        }
+        if !seen_digit {
+            return Err(format!("no digits in {text:?}"));
+        }
+        natural::trim(&mut digits);
+        if digits.is_empty() {
+            return Ok(Self::zero(precision));
+        }
+
+        let power = decimal_exponent.saturating_sub(fraction_digits);
+        let limit = PARSE_EXPONENT_ALLOWANCE + (precision as f64 * std::f64::consts::LOG10_2) as u64 + digit_count;
+        if power.unsigned_abs() > limit {
+            return Err(format!("exponent out of range in {text:?}"));
+        }
+        let power_magnitude =
+            u32::try_from(power.unsigned_abs()).map_err(|_| format!("exponent out of range in {text:?}"))?;
+        let power_of_ten = natural::pow_small(10, power_magnitude);
+        if power >= 0 {
+            return Ok(Self::rounded(negative, natural::mul(&digits, &power_of_ten), 0, precision));
+        }
+
+        Ok(Self::divided(negative, &digits, 0, &power_of_ten, 0, precision))
+    }
+
+    /// Rounds `±(numerator × 2^numerator_exponent) / (denominator × 2^denominator_exponent)`
+    /// to `precision` bits.
+    fn divided(
+        negative: bool,
+        numerator: &[u32],
+        numerator_exponent: i64,
+        denominator: &[u32],
+        denominator_exponent: i64,
+        precision: u32,
+    ) -> Self {
+        // Shift the numerator so that the quotient carries two bits more than needed.
+        let shift = (precision as i64 + 2 + natural::bits(denominator) as i64
+            - natural::bits(numerator) as i64)
+            .max(0) as u64;
+        let (mut quotient, remainder) = natural::div_rem(&natural::shl(numerator, shift), denominator);
+        let mut exponent = numerator_exponent - denominator_exponent - shift as i64;
+
+        // Keep an inexact quotient from rounding as if it were exact.
+        quotient = natural::shl(&quotient, 1);
+        exponent -= 1;
+        if !remainder.is_empty() {
+            quotient = natural::add_small(&quotient, 1);
+        }
+
+        Self::rounded(negative, quotient, exponent, precision)
+    }
+
+    /// Mantissa precision in bits.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// Returns this value rounded to a new precision.
+    pub fn with_precision(&self, precision: u32) -> Self {
+        Self::rounded(self.negative, self.mantissa.clone(), self.exponent, precision)
+    }
+
+    /// Returns `true` if the value is zero.
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_empty()
+    }
+
+    /// Returns `true` if the value is strictly negative.
+    pub fn is_negative(&self) -> bool {
+        self.negative && !self.is_zero()
+    }
+
+    /// Position of the most significant bit: the value lies in `[2^(top - 1), 2^top)`.
+    /// Meaningless for zero.
+    pub fn top_bit(&self) -> i64 {
+        self.exponent + natural::bits(&self.mantissa) as i64
+    }
+
+    /// Returns the opposite value.
+    pub fn negate(&self) -> Self {
+        BigFloat {
+            negative: !self.negative,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the absolute value.
+    pub fn abs(&self) -> Self {
+        BigFloat {
+            negative: false,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the value multiplied by `2^power`, which is exact within the exponent range.
+    pub fn mul_pow2(&self, power: i64) -> Self {
+        if self.is_zero() {
+            return self.clone();
+        }
+        let power = power.clamp(-4 * MAX_TOP_BIT, 4 * MAX_TOP_BIT);
+        Self::rounded(self.negative, self.mantissa.clone(), self.exponent + power, self.precision)
+    }
+
+    fn compare_magnitude(&self, other: &Self) -> Ordering {
+        match (self.is_zero(), other.is_zero()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+        match self.top_bit().cmp(&other.top_bit()) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+        let exponent = self.exponent.min(other.exponent);
+        let a = natural::shl(&self.mantissa, (self.exponent - exponent) as u64);
+        let b = natural::shl(&other.mantissa, (other.exponent - exponent) as u64);
+        natural::cmp(&a, &b)
+    }
+
+    /// Returns the sum of two numbers.
+    pub fn add(&self, other: &Self) -> Self {
+        let precision = self.precision.max(other.precision);
+        if other.is_zero() {
+            return self.with_precision(precision);
+        }
+        if self.is_zero() {
+            return other.with_precision(precision);
+        }
+
+        // An operand far below the last bit of the other one only matters for rounding.
+        let (large, small) = if self.compare_magnitude(other) == Ordering::Less {
+            (other, self)
+        } else {
+            (self, other)
+        };
+        if large.top_bit() - small.top_bit() > precision as i64 + 2 {
+            // The small operand is below one unit of the lowest bit kept here, so it only
+            // decides the rounding: count it as a single sticky unit.
+            let floor = large.top_bit() - precision as i64 - 4;
+            let shifted = natural::shl(&large.mantissa, (large.exponent - floor) as u64);
+            let magnitude = if large.negative == small.negative {
+                natural::add_small(&shifted, 1)
+            } else {
+                natural::sub(&shifted, &[1])
+            };
+            return Self::rounded(large.negative, magnitude, floor, precision);
+        }
+
+        let exponent = large.exponent.min(small.exponent);
+        let a = natural::shl(&large.mantissa, (large.exponent - exponent) as u64);
+        let b = natural::shl(&small.mantissa, (small.exponent - exponent) as u64);
+        let magnitude = if large.negative == small.negative {
+            natural::add(&a, &b)
+        } else {
+            natural::sub(&a, &b)
+        };
+        Self::rounded(large.negative, magnitude, exponent, precision)
+    }
+
+    /// Returns the difference of two numbers.
+    pub fn subtract(&self, other: &Self) -> Self {
+        self.add(&other.negate())
+    }
+
+    /// Returns the product of two numbers.
+    pub fn multiply(&self, other: &Self) -> Self {
+        Self::rounded(
+            self.negative != other.negative,
+            natural::mul(&self.mantissa, &other.mantissa),
+            self.exponent + other.exponent,
+            self.precision.max(other.precision),
+        )
+    }
+
+    /// Returns the quotient of two numbers.
+    ///
+    /// ```
+    /// use common::BigFloat;
+    ///
+    /// let third = BigFloat::from_i64(1, 256).divide(&BigFloat::from_i64(3, 256));
+    /// assert_eq!(third.to_decimal_string(60), format!("3.{}e-1", "3".repeat(59)));
+    /// let product = BigFloat::parse("1.5e-300", 256).unwrap().multiply(&BigFloat::parse("-4e-300", 256).unwrap());
+    /// assert_eq!(product.to_decimal_string(20), "-6e-600");
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if `other` is zero.
+    pub fn divide(&self, other: &Self) -> Self {
+        let precision = self.precision.max(other.precision);
+        if self.is_zero() {
+            return Self::zero(precision);
+        }
+        Self::divided(
+            self.negative != other.negative,
+            &self.mantissa,
+            self.exponent,
+            &other.mantissa,
+            other.exponent,
+            precision,
+        )
+    }
+
+    /// Returns the square root.
+    ///
+    /// ```
+    /// use common::BigFloat;
+    ///
+    /// let two = BigFloat::from_i64(2, 256);
+    /// assert_eq!(
+    ///     two.sqrt().to_decimal_string(60),
+    ///     "1.41421356237309504880168872420969807856967187537694807317668",
+    /// );
+    /// assert_eq!(BigFloat::parse("6.25e-200", 128).unwrap().sqrt().to_decimal_string(10), "2.5e-100");
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the value is negative.
+    pub fn sqrt(&self) -> Self {
+        assert!(!self.is_negative(), "square root of a negative BigFloat");
+        if self.is_zero() {
+            return self.clone();
+        }
+        // Widen the mantissa to twice the precision with an even exponent.
+        let mut shift = (2 * self.precision as i64 + 4 - natural::bits(&self.mantissa) as i64).max(0);
+        if (self.exponent - shift) % 2 != 0 {
+            shift += 1;
+        }
+        let widened = natural::shl(&self.mantissa, shift as u64);
+        let root = natural::sqrt(&widened);
+        let exact = natural::cmp(&natural::mul(&root, &root), &widened) == Ordering::Equal;
+
+        let mut mantissa = natural::shl(&root, 1);
+        if !exact {
+            mantissa = natural::add_small(&mantissa, 1);
+        }
+        Self::rounded(false, mantissa, (self.exponent - shift) / 2 - 1, self.precision)
+    }
+
+    /// Returns the nearest `f64`, ties to even, saturating to infinity or zero outside of
+    /// its range.
+    pub fn to_f64(&self) -> f64 {
+        if self.is_zero() {
+            return 0.0;
+        }
+        let sign = if self.negative { -1.0 } else { 1.0 };
+
+        // Round once, to the bits the f64 keeps at this magnitude: 53, fewer for subnormals.
+        let kept_bits = (self.top_bit() + 1074).min(53);
+        if kept_bits <= 0 {
+            // Above half the smallest subnormal only when it lies in [2^-1075, 2^-1074) and
+            // is not exactly 2^-1075.
+            let bits = natural::bits(&self.mantissa);
+            let above_half = kept_bits == 0 && natural::any_below(&self.mantissa, bits - 1);
+            return sign * if above_half { f64::from_bits(1) } else { 0.0 };
+        }
+        let rounded = Self::rounded(false, self.mantissa.clone(), self.exponent, kept_bits as u32);
+        let mantissa = rounded.mantissa.first().copied().unwrap_or(0) as u64
+            | (rounded.mantissa.get(1).copied().unwrap_or(0) as u64) << 32;
+
+        // Scaling by powers of two is exact until the value overflows.
+        let mut value = mantissa as f64;
+        let mut exponent = rounded.exponent;
+        while exponent > 0 && value.is_finite() {
+            let step = exponent.min(1000);
+            value *= 2f64.powi(step as i32);
+            exponent -= step;
+        }
+        while exponent < 0 && value != 0.0 {
+            let step = exponent.max(-1000);
+            value *= 2f64.powi(step as i32);
+            exponent -= step;
+        }
+        sign * value
+    }
+
+    /// Formats the value in decimal scientific notation with `digits` significant digits.
+    pub fn to_decimal_string(&self, digits: usize) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let digits = digits.max(1);
+
+        // Estimate the decimal exponent, then correct it if the estimate was off by one.
+        let mut decimal_exponent = ((self.top_bit() - 1) as f64 * std::f64::consts::LOG10_2).floor() as i64;
+        let scaled = loop {
+            let scale = digits as i64 - 1 - decimal_exponent;
+            let mut numerator = self.mantissa.clone();
+            let mut denominator = vec![1u32];
+            if self.exponent >= 0 {
+                numerator = natural::shl(&numerator, self.exponent as u64);
+            } else {
+                denominator = natural::shl(&denominator, self.exponent.unsigned_abs());
+            }
+            let power_of_ten = natural::pow_small(10, scale.unsigned_abs() as u32);
+            if scale >= 0 {
+                numerator = natural::mul(&numerator, &power_of_ten);
+            } else {
+                denominator = natural::mul(&denominator, &power_of_ten);
+            }
+
+            // Round to nearest: (2 n + d) / (2 d).
+            let (rounded, _) = natural::div_rem(
+                &natural::add(&natural::shl(&numerator, 1), &denominator),
+                &natural::shl(&denominator, 1),
+            );
+            if natural::cmp(&rounded, &natural::pow_small(10, digits as u32)) != Ordering::Less {
+                decimal_exponent += 1;
+            } else if natural::cmp(&rounded, &natural::pow_small(10, digits as u32 - 1)) == Ordering::Less {
+                decimal_exponent -= 1;
+            } else {
+                break rounded;
+            }
+        };
+
+        let mut decimal = Vec::with_capacity(digits);
+        let mut rest = scaled;
+        while !rest.is_empty() {
+            let (quotient, remainder) = natural::div_rem(&rest, &[10]);
+            decimal.push(b'0' + remainder.first().copied().unwrap_or(0) as u8);
+            rest = quotient;
+        }
+        decimal.reverse();
+        while decimal.len() > 1 && decimal.last() == Some(&b'0') {
+            decimal.pop();
+        }
+
+        let mut text = String::new();
+        if self.negative {
+            text.push('-');
+        }
+        text.push(decimal[0] as char);
+        if decimal.len() > 1 {
+            text.push('.');
+            text.push_str(std::str::from_utf8(&decimal[1..]).expect("decimal digits are ASCII"));
+        }
+        if decimal_exponent != 0 {
+            text.push_str(&format!("e{decimal_exponent}"));
+        }
+        text
+    }
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for BigFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let ordering = match (self.is_negative(), other.is_negative()) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.compare_magnitude(other),
+            (true, true) => other.compare_magnitude(self),
+        };
+        Some(ordering)
+    }
+}
+
+impl fmt::Display for BigFloat {
+    /// Formats the value with enough decimal digits to identify it at its precision.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = (self.precision as f64 * std::f64::consts::LOG10_2).ceil() as usize + 1;
+        f.write_str(&self.to_decimal_string(digits))
+    }
+}
@@ -2,19 +2,44 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::float::Float;
+
 /// Represents a complex number with real (`re`) and imaginary (`im`) parts.
+///
+/// The parts are `f64` unless another [`Float`] type is given.
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
-pub struct Complex {
+pub struct Complex<T = f64> {
   /// Real part of the complex number.
-  pub re: f64,
+  pub re: T,
   /// Imaginary part of the complex number.
-  pub im: f64,
+  pub im: T,
+}
+
+impl<T: Float> Complex<T> {
+  /// Converts an `f64` complex number, rounding it when `T` is narrower.
+  pub fn from_f64(z: Complex) -> Self {
+    Complex {
+      re: T::from_f64(z.re),
+      im: T::from_f64(z.im),
+    }
+  }
+
+  /// Returns the nearest `f64` complex number.
+  pub fn to_f64(&self) -> Complex {
+    Complex {
+      re: self.re.to_f64(),
+      im: self.im.to_f64(),
+    }
+  }
 }
 
 /// Trait defining basic operations for complex numbers.
 pub trait ComplexTrait {
+  /// Type of the real and imaginary parts.
+  type Real;
+
   /// Constructs a new complex number with the given real and imaginary parts.
-  fn new(re: f64, im: f64) -> Self;
+  fn new(re: Self::Real, im: Self::Real) -> Self;
 
   /// Returns the sum of two complex numbers.
   fn add(&self, other: &Self) -> Self;
@@ -22,31 +47,39 @@ pub trait ComplexTrait {
   /// Returns the argument (angle) of the complex number in radians.
   fn argument(&self) -> f64;
 
+  /// Returns the cosine of the complex number using Euler's formula.
+  fn cosine(&self) -> Self;
+
   /// Divides the complex number by another and returns the result.
   fn divide(&self, other: Self) -> Self;
 
+  /// Returns the complex exponential `e^z`.
+  fn exponential(&self) -> Self;
+
   /// Returns the product of two complex numbers.
   fn multiply(&self, other: &Self) -> Self;
 
   /// Returns the sine of the complex number using Euler's formula.
-  fn sine(&self) -> Complex;
+  fn sine(&self) -> Self;
 
   /// Returns the square of the complex number.
-  fn square(&self) -> Complex;
+  fn square(&self) -> Self;
 
   /// Returns the squared magnitude (norm) of the complex number.
-  fn square_norm(&self) -> f64;
+  fn square_norm(&self) -> Self::Real;
 
   /// Returns the difference between two complex numbers.
   fn subtract(&self, other: &Self) -> Self;
 }
 
-impl ComplexTrait for Complex {
-  fn new(re: f64, im: f64) -> Self {
+impl<T: Float> ComplexTrait for Complex<T> {
+  type Real = T;
+
+  fn new(re: T, im: T) -> Self {
     Complex { re, im }
   }
 
-  fn add(&self, other: &Complex) -> Complex {
+  fn add(&self, other: &Complex<T>) -> Complex<T> {
     Complex {
       re: self.re + other.re,
       im: self.im + other.im,
@@ -54,10 +87,17 @@ impl ComplexTrait for Complex {
   }
 
   fn argument(&self) -> f64 {
-    self.im.atan2(self.re)
+    self.im.to_f64().atan2(self.re.to_f64())
   }
 
-  fn divide(&self, other: Complex) -> Complex {
+  fn cosine(&self) -> Complex<T> {
+    Complex {
+      re: self.re.cos() * self.im.cosh(),
+      im: -self.re.sin() * self.im.sinh(),
+    }
+  }
+
+  fn divide(&self, other: Complex<T>) -> Complex<T> {
     let divisor = other.re * other.re + other.im * other.im;
     Complex {
       re: (self.re * other.re + self.im * other.im) / divisor,
@@ -65,32 +105,40 @@ impl ComplexTrait for Complex {
     }
   }
 
-  fn multiply(&self, other: &Complex) -> Complex {
+  fn exponential(&self) -> Complex<T> {
+    let modulus = self.re.exp();
+    Complex {
+      re: modulus * self.im.cos(),
+      im: modulus * self.im.sin(),
+    }
+  }
+
+  fn multiply(&self, other: &Complex<T>) -> Complex<T> {
     Complex {
       re: self.re * other.re - self.im * other.im,
       im: self.re * other.im + self.im * other.re,
     }
   }
 
-  fn sine(&self) -> Complex {
+  fn sine(&self) -> Complex<T> {
     Complex {
       re: self.re.sin() * self.im.cosh(),
       im: self.re.cos() * self.im.sinh(),
     }
   }
 
-  fn square(&self) -> Complex {
+  fn square(&self) -> Complex<T> {
     Complex {
       re: self.re * self.re - self.im * self.im,
-      im: 2.0 * self.re * self.im,
+      im: T::from_f64(2.0) * self.re * self.im,
     }
   }
 
-  fn square_norm(&self) -> f64 {
+  fn square_norm(&self) -> T {
     self.re * self.re + self.im * self.im
   }
 
-  fn subtract(&self, other: &Complex) -> Complex {
+  fn subtract(&self, other: &Complex<T>) -> Complex<T> {
     Complex {
       re: self.re - other.re,
       im: self.im - other.im,
@@ -14,7 +14,7 @@ pub struct Pixels {
 }
 
 /// Represents a 2D point in Cartesian coordinates.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Point {
     /// The X coordinate.
     pub x: f64,
@@ -24,7 +24,7 @@ pub struct Point {
 
 /// Represents an unsigned 8-bit data block defined by an offset and count.
 /// Useful for referencing raw byte data (e.g., grayscale image data).
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct U8Data {
     /// Offset in the data buffer.
     pub offset: u32,
@@ -42,7 +42,7 @@ pub struct Resolution {
 }
 
 /// Represents a 2D range using a bounding box defined by two points (min and max).
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Range {
     /// Lower-left corner of the range.
     pub min: Point,
@@ -62,6 +62,7 @@ pub struct PixelData {
 /// Represents the intensity and iteration count of a pixel in fractal rendering.
 /// - `zn` can represent the final magnitude or value at divergence.
 /// - `count` usually refers to the number of iterations before divergence.
+#[derive(Debug, Clone, Copy)]
 pub struct PixelIntensity {
     /// Final value (e.g., magnitude or divergence measure).
     pub zn: f32,
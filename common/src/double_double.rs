@@ -0,0 +1,226 @@
+//! Double-double arithmetic: a real number stored as the unevaluated sum of two `f64`.
+//!
+//! The pair `hi + lo` with `|lo| <= ulp(hi) / 2` carries about 106 bits of mantissa, for
+//! roughly twice the cost of hardware `f64` in additions and multiplications. The error-free
+//! transformations follow Dekker, Knuth and the QD library of Hida, Li and Bailey.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::float::Float;
+
+/// A real number with about 106 bits of precision, as the sum `hi + lo` of two `f64`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct DoubleDouble {
+    /// Leading part, the `f64` nearest to the value.
+    pub hi: f64,
+    /// Trailing part, the rounding error of `hi`.
+    pub lo: f64,
+}
+
+/// π / 2 to double-double precision.
+const FRAC_PI_2: DoubleDouble = DoubleDouble {
+    hi: std::f64::consts::FRAC_PI_2,
+    lo: 6.123233995736766e-17,
+};
+
+/// ln 2 to double-double precision.
+const LN_2: DoubleDouble = DoubleDouble {
+    hi: std::f64::consts::LN_2,
+    lo: 2.3190468138462996e-17,
+};
+
+/// Relative size below which the terms of a Taylor series are dropped.
+const SERIES_EPSILON: f64 = 1e-33;
+
+/// Number of halvings applied to the argument of the exponential before its series.
+const EXP_HALVINGS: i32 = 9;
+
+/// Returns `a + b` exactly as a rounded sum and its error.
+fn two_sum(a: f64, b: f64) -> DoubleDouble {
+    let hi = a + b;
+    let b_virtual = hi - a;
+    let lo = (a - (hi - b_virtual)) + (b - b_virtual);
+    DoubleDouble { hi, lo }
+}
+
+/// Same as [`two_sum`], assuming `|a| >= |b|`.
+fn quick_two_sum(a: f64, b: f64) -> DoubleDouble {
+    let hi = a + b;
+    DoubleDouble { hi, lo: b - (hi - a) }
+}
+
+/// Returns `a * b` exactly as a rounded product and its error.
+fn two_product(a: f64, b: f64) -> DoubleDouble {
+    let hi = a * b;
+    DoubleDouble { hi, lo: a.mul_add(b, -hi) }
+}
+
+impl DoubleDouble {
+    /// Builds a double-double from an `f64`, exactly.
+    pub fn new(value: f64) -> Self {
+        DoubleDouble { hi: value, lo: 0.0 }
+    }
+
+    /// Returns the value multiplied by `2^power`, which is exact within the `f64` range.
+    fn mul_pow2(self, power: i32) -> Self {
+        let half = 2f64.powi(power / 2);
+        let rest = 2f64.powi(power - power / 2);
+        DoubleDouble {
+            hi: self.hi * half * rest,
+            lo: self.lo * half * rest,
+        }
+    }
+
+    /// Sums `first + Σ term_k` where `term_k = term_{k-1} * step / (k-th divisor)`,
+    /// until the terms become negligible.
+    fn series(first: Self, step: Self, divisor: impl Fn(u32) -> f64) -> Self {
+        let mut sum = first;
+        let mut term = first;
+        for k in 1.. {
+            term = term * step / DoubleDouble::new(divisor(k));
+            sum = sum + term;
+            if term.hi.abs() <= SERIES_EPSILON * sum.hi.abs() {
+                break;
+            }
+        }
+        sum
+    }
+
+    /// Returns `e^x - 1` for `|x|` below 1/2, where it is free of cancellation.
+    fn exp_minus_one_small(self) -> Self {
+        Self::series(self, self, |k| (k + 1) as f64)
+    }
+
+    /// Returns the sine and the cosine, reducing the argument by multiples of π/2.
+    fn sin_cos(self) -> (Self, Self) {
+        let quadrant = (self / FRAC_PI_2).hi.round();
+        let reduced = self - FRAC_PI_2 * DoubleDouble::new(quadrant);
+        let square = reduced * reduced;
+        let sin = Self::series(reduced, -square, |k| (2 * k * (2 * k + 1)) as f64);
+        let cos = Self::series(DoubleDouble::new(1.0), -square, |k| ((2 * k - 1) * 2 * k) as f64);
+
+        match (quadrant as i64).rem_euclid(4) {
+            0 => (sin, cos),
+            1 => (cos, -sin),
+            2 => (-sin, -cos),
+            _ => (-cos, sin),
+        }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let high = two_sum(self.hi, other.hi);
+        let low = two_sum(self.lo, other.lo);
+        let sum = quick_two_sum(high.hi, high.lo + low.hi);
+        quick_two_sum(sum.hi, sum.lo + low.lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        let product = two_product(self.hi, other.hi);
+        quick_two_sum(product.hi, product.lo + (self.hi * other.lo + self.lo * other.hi))
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn div(self, other: DoubleDouble) -> DoubleDouble {
+        let first = self.hi / other.hi;
+        let remainder = self - other * DoubleDouble::new(first);
+        let second = remainder.hi / other.hi;
+        let remainder = remainder - other * DoubleDouble::new(second);
+        let third = remainder.hi / other.hi;
+        quick_two_sum(first, second) + DoubleDouble::new(third)
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Float for DoubleDouble {
+    /// `2^-104`: the 106-bit mantissa loses up to two bits to the sign of `lo`.
+    const EPSILON: f64 = 4.930380657631324e-32;
+
+    fn from_f64(value: f64) -> Self {
+        DoubleDouble::new(value)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    fn sqrt(self) -> Self {
+        if self.hi <= 0.0 {
+            return DoubleDouble::new(self.hi.sqrt());
+        }
+        // One Newton step from the f64 root doubles its precision.
+        let inverse = 1.0 / self.hi.sqrt();
+        let root = self.hi * inverse;
+        let correction = (self - two_product(root, root)).hi * (inverse * 0.5);
+        two_sum(root, correction)
+    }
+
+    fn sin(self) -> Self {
+        self.sin_cos().0
+    }
+
+    fn cos(self) -> Self {
+        self.sin_cos().1
+    }
+
+    fn sinh(self) -> Self {
+        if self.hi.abs() < 0.5 {
+            let square = self * self;
+            return Self::series(self, square, |k| (2 * k * (2 * k + 1)) as f64);
+        }
+        let exp = self.exp();
+        (exp - DoubleDouble::new(1.0) / exp).mul_pow2(-1)
+    }
+
+    fn cosh(self) -> Self {
+        let exp = self.exp();
+        (exp + DoubleDouble::new(1.0) / exp).mul_pow2(-1)
+    }
+
+    fn exp(self) -> Self {
+        if self.hi > 709.8 {
+            return DoubleDouble::new(f64::INFINITY);
+        }
+        if self.hi < -745.2 {
+            return DoubleDouble::new(0.0);
+        }
+
+        // e^x = 2^k e^r with |r| <= ln 2 / 2, and e^r computed from r / 2^9 by squaring
+        // e^s - 1 back, which keeps the small result free of cancellation.
+        let k = (self.hi / LN_2.hi).round();
+        let reduced = (self - LN_2 * DoubleDouble::new(k)).mul_pow2(-EXP_HALVINGS);
+        let mut exp_minus_one = reduced.exp_minus_one_small();
+        for _ in 0..EXP_HALVINGS {
+            exp_minus_one = exp_minus_one * (exp_minus_one + DoubleDouble::new(2.0));
+        }
+        (exp_minus_one + DoubleDouble::new(1.0)).mul_pow2(k as i32)
+    }
+}
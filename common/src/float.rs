@@ -0,0 +1,88 @@
+//! Real number types the complex arithmetic and the fractals can be computed with.
+
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A floating-point real type: `f32`, `f64` or [`crate::DoubleDouble`].
+pub trait Float:
+    Copy
+    + Debug
+    + PartialOrd
+    + Send
+    + Sync
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    /// Relative spacing of consecutive values around `1`, as an `f64`.
+    const EPSILON: f64;
+
+    /// Converts an `f64`, rounding it when the type is narrower.
+    fn from_f64(value: f64) -> Self;
+
+    /// Returns the nearest `f64`.
+    fn to_f64(self) -> f64;
+
+    /// Returns the square root.
+    fn sqrt(self) -> Self;
+
+    /// Returns the sine.
+    fn sin(self) -> Self;
+
+    /// Returns the cosine.
+    fn cos(self) -> Self;
+
+    /// Returns the hyperbolic sine.
+    fn sinh(self) -> Self;
+
+    /// Returns the hyperbolic cosine.
+    fn cosh(self) -> Self;
+
+    /// Returns `e^self`.
+    fn exp(self) -> Self;
+}
+
+macro_rules! impl_float {
+    ($type:ty) => {
+        impl Float for $type {
+            const EPSILON: f64 = <$type>::EPSILON as f64;
+
+            fn from_f64(value: f64) -> Self {
+                value as $type
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn sqrt(self) -> Self {
+                <$type>::sqrt(self)
+            }
+
+            fn sin(self) -> Self {
+                <$type>::sin(self)
+            }
+
+            fn cos(self) -> Self {
+                <$type>::cos(self)
+            }
+
+            fn sinh(self) -> Self {
+                <$type>::sinh(self)
+            }
+
+            fn cosh(self) -> Self {
+                <$type>::cosh(self)
+            }
+
+            fn exp(self) -> Self {
+                <$type>::exp(self)
+            }
+        }
+    };
+}
+
+impl_float!(f32);
+impl_float!(f64);
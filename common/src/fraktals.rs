@@ -9,7 +9,7 @@ use crate::complex::Complex;
 
 /// Describes a fractal configuration using one of the supported types.
 /// Each variant represents a different fractal family with its own parameters.
-#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub enum FractalDescriptor {
     /// Julia set fractal with a complex constant `c` and a divergence threshold.
     Julia(JuliaDescriptor),
@@ -17,10 +17,16 @@ pub enum FractalDescriptor {
     IteratedSinZ(IteratedSinZDescriptor),
     /// Mandelbrot set fractal.
     Mandelbrot(MandelbrotDescriptor),
+    /// Mandelbrot set fractal computed by perturbation around a high-precision center.
+    MandelbrotDeepZoom(MandelbrotDeepZoomDescriptor),
+    /// Multibrot set fractal, the Mandelbrot set of `z^d + c`.
+    Multibrot(MultibrotDescriptor),
     /// Newton-Raphson fractal for the function `z^3 - 1`.
     NewtonRaphsonZ3(NewtonRaphsonZ3Descriptor),
     /// Newton-Raphson fractal for the function `z^4 - 1`.
     NewtonRaphsonZ4(NewtonRaphsonZ4Descriptor),
+    /// Newton-Raphson fractal for a transcendental function with infinitely many roots.
+    NewtonRaphsonTranscendental(NewtonRaphsonTranscendentalDescriptor),
     /// Nova variant of Newton-Raphson for `z^3 - 1`.
     NovaNewtonZ3(NovaNewtonRaphsonZ3Descriptor),
     /// Nova variant of Newton-Raphson for `z^4 - 1`.
@@ -33,6 +39,9 @@ pub enum FractalDescriptor {
 pub struct IteratedSinZDescriptor {
     /// Constant complex value added at each iteration.
     pub c: Complex,
+    /// Plane the pixels are mapped to; defaults to the dynamical plane with constant `c`.
+    #[serde(default)]
+    pub plane: Option<Plane>,
 }
 
 /// Descriptor for the Julia set fractal.
@@ -43,13 +52,64 @@ pub struct JuliaDescriptor {
     pub c: Complex,
     /// Divergence threshold squared (used for performance optimization).
     pub divergence_threshold_square: f64,
+    /// Plane the pixels are mapped to; defaults to the dynamical plane with constant `c`.
+    #[serde(default)]
+    pub plane: Option<Plane>,
 }
 
 /// Descriptor for the Mandelbrot set fractal.
-/// This struct has no parameters because all Mandelbrot calculations
-/// start with `z = 0` and iterate `z = z^2 + c` for each pixel.
+/// By default all Mandelbrot calculations start with `z = 0`
+/// and iterate `z = z^2 + c` for each pixel.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct MandelbrotDescriptor {
+    /// Plane the pixels are mapped to; defaults to the parameter plane starting at `z = 0`.
+    #[serde(default)]
+    pub plane: Option<Plane>,
+}
+
+/// Descriptor for the deep-zoom Mandelbrot set fractal.
+///
+/// Deep zooms cannot be described by `f64` ranges, so the center of the view is given
+/// as decimal strings and the range of a task holds offsets from this center.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MandelbrotDeepZoomDescriptor {
+    /// Real part of the center, as a decimal string.
+    pub center_re: String,
+    /// Imaginary part of the center, as a decimal string.
+    pub center_im: String,
+}
+
+/// Descriptor for the Multibrot set fractal.
+/// Iterates `z = z^exponent + c` from `z = 0` for each pixel.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct MultibrotDescriptor {
+    /// Power `d` of the iterated polynomial, at least 2.
+    pub exponent: u32,
+    /// Plane the pixels are mapped to; defaults to the parameter plane starting at `z = 0`.
+    #[serde(default)]
+    pub plane: Option<Plane>,
+}
+
+/// Selects which plane the pixels of an escape-time fractal are mapped to.
+///
+/// Every escape-time formula `z = f(z, c)` can be drawn either as a Mandelbrot-style
+/// map of its parameter `c`, or as a Julia set of its starting point `z0`.
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
-pub struct MandelbrotDescriptor {}
+pub enum Plane {
+    /// Pixels give the parameter `c`; every orbit starts at `z0`.
+    /// Without `z0` the orbit starts at the critical point of the formula,
+    /// any other value renders a "perturbed" map.
+    Parameter {
+        /// Starting point shared by every orbit.
+        #[serde(default)]
+        z0: Option<Complex>,
+    },
+    /// Pixels give the starting point `z0`; the parameter `c` is fixed.
+    Dynamical {
+        /// Parameter shared by every orbit.
+        c: Complex,
+    },
+}
 
 /// Descriptor for the Newton-Raphson fractal solving `z^3 - 1 = 0`.
 /// It visualizes how initial points converge to one of the roots of the function.
@@ -60,6 +120,33 @@ pub struct NewtonRaphsonZ3Descriptor {}
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 pub struct NewtonRaphsonZ4Descriptor {}
 
+/// Descriptor for the Newton-Raphson fractal of a transcendental function.
+/// Roots are not known in advance: they are discovered while iterating and
+/// identified by the cell of the `root_tolerance` grid holding them.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct NewtonRaphsonTranscendentalDescriptor {
+    /// Function whose roots are searched.
+    pub function: TranscendentalFunction,
+    /// Width of the grid cells within which converged points are considered the same root.
+    #[serde(default = "default_root_tolerance")]
+    pub root_tolerance: f64,
+}
+
+/// Transcendental functions supported by the Newton-Raphson fractal.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub enum TranscendentalFunction {
+    /// `sin(z)`, roots at `kπ`.
+    Sin,
+    /// `exp(z) - 1`, roots at `2kπi`.
+    ExpMinusOne,
+    /// `z·sin(z) - 1`, roots close to `kπ` for large `|k|`.
+    ZSinZMinusOne,
+}
+
+fn default_root_tolerance() -> f64 {
+    1e-6
+}
+
 /// Descriptor for the Nova Newton-Raphson fractal based on `z^3 - 1`.
 /// Nova fractals are a variation where a constant is introduced to break symmetry.
 #[derive(Debug, Deserialize, Serialize, Clone, Copy)]
@@ -0,0 +1,141 @@
+//! Interval arithmetic on `f64` with outward rounding.
+//!
+//! Every operation returns an interval containing the exact result of the operation on
+//! any values of its operands. Floating-point operations round to nearest, within half
+//! an ulp of the exact result, so widening their result by one ulp on each side makes
+//! the enclosure rigorous without switching the rounding mode of the processor.
+
+use std::fmt;
+
+/// A closed interval `[lo, hi]` of real numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    /// Lower bound.
+    pub lo: f64,
+    /// Upper bound.
+    pub hi: f64,
+}
+
+impl Interval {
+    /// Creates the interval `[lo, hi]`, the bounds being swapped when given in the wrong order.
+    pub fn new(lo: f64, hi: f64) -> Self {
+        Interval {
+            lo: lo.min(hi),
+            hi: lo.max(hi),
+        }
+    }
+
+    /// Creates the interval holding exactly `value`.
+    pub fn point(value: f64) -> Self {
+        Interval { lo: value, hi: value }
+    }
+
+    /// Widens a rounded-to-nearest enclosure by one ulp on each side.
+    fn outward(lo: f64, hi: f64) -> Self {
+        Interval {
+            lo: lo.next_down(),
+            hi: hi.next_up(),
+        }
+    }
+
+    /// Returns the sum of two intervals.
+    pub fn add(&self, other: &Interval) -> Interval {
+        Interval::outward(self.lo + other.lo, self.hi + other.hi)
+    }
+
+    /// Returns the difference of two intervals.
+    pub fn subtract(&self, other: &Interval) -> Interval {
+        Interval::outward(self.lo - other.hi, self.hi - other.lo)
+    }
+
+    /// Returns the product of two intervals.
+    pub fn multiply(&self, other: &Interval) -> Interval {
+        let products = [
+            self.lo * other.lo,
+            self.lo * other.hi,
+            self.hi * other.lo,
+            self.hi * other.hi,
+        ];
+        let lo = products.iter().copied().fold(f64::INFINITY, f64::min);
+        let hi = products.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Interval::outward(lo, hi)
+    }
+
+    /// Returns the square of the interval, which unlike `self.multiply(self)` never
+    /// goes below zero.
+    pub fn square(&self) -> Interval {
+        let (lo, hi) = (self.mignitude(), self.magnitude());
+        Interval::outward(lo * lo, hi * hi).max_zero()
+    }
+
+    /// Returns twice the interval, which is exact: a bound can only overflow to infinity.
+    pub fn double(&self) -> Interval {
+        Interval {
+            lo: self.lo * 2.0,
+            hi: self.hi * 2.0,
+        }
+    }
+
+    /// Returns the negated interval, which is exact.
+    pub fn negate(&self) -> Interval {
+        Interval {
+            lo: -self.hi,
+            hi: -self.lo,
+        }
+    }
+
+    /// Clamps the lower bound to zero.
+    fn max_zero(self) -> Interval {
+        Interval {
+            lo: self.lo.max(0.0),
+            hi: self.hi,
+        }
+    }
+
+    /// Returns the largest absolute value of the interval.
+    pub fn magnitude(&self) -> f64 {
+        self.lo.abs().max(self.hi.abs())
+    }
+
+    /// Returns the smallest absolute value of the interval, `0` when it contains zero.
+    pub fn mignitude(&self) -> f64 {
+        if self.lo > 0.0 {
+            self.lo
+        } else if self.hi < 0.0 {
+            -self.hi
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the middle of the interval, rounded.
+    pub fn midpoint(&self) -> f64 {
+        self.lo / 2.0 + self.hi / 2.0
+    }
+
+    /// Returns an upper bound of the width of the interval.
+    pub fn width(&self) -> f64 {
+        (self.hi - self.lo).next_up()
+    }
+
+    /// Tells whether `value` lies in the interval.
+    pub fn contains(&self, value: f64) -> bool {
+        self.lo <= value && value <= self.hi
+    }
+
+    /// Tells whether the interval lies in the interior of `other`.
+    pub fn is_interior_to(&self, other: &Interval) -> bool {
+        other.lo < self.lo && self.hi < other.hi
+    }
+
+    /// Tells whether both bounds are finite, which fails once an enclosure overflows.
+    pub fn is_finite(&self) -> bool {
+        self.lo.is_finite() && self.hi.is_finite()
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:e}, {:e}]", self.lo, self.hi)
+    }
+}
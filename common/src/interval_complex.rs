@@ -0,0 +1,107 @@
+//! Rectangular complex intervals built on [`Interval`].
+
+use crate::complex::Complex;
+use crate::interval::Interval;
+
+/// A rectangle of the complex plane, the product of a real and an imaginary interval.
+///
+/// Operations return a rectangle containing the exact result of the operation on any
+/// complex numbers of the operands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntervalComplex {
+    /// Real parts.
+    pub re: Interval,
+    /// Imaginary parts.
+    pub im: Interval,
+}
+
+impl IntervalComplex {
+    /// Creates the rectangle `re × im`.
+    pub fn new(re: Interval, im: Interval) -> Self {
+        IntervalComplex { re, im }
+    }
+
+    /// Creates the rectangle holding exactly `z`.
+    pub fn point(z: Complex) -> Self {
+        IntervalComplex {
+            re: Interval::point(z.re),
+            im: Interval::point(z.im),
+        }
+    }
+
+    /// Creates the rectangle with opposite corners `min` and `max`.
+    pub fn from_corners(min: Complex, max: Complex) -> Self {
+        IntervalComplex {
+            re: Interval::new(min.re, max.re),
+            im: Interval::new(min.im, max.im),
+        }
+    }
+
+    /// Creates the square centred on `center` with the given half-width.
+    pub fn around(center: Complex, radius: f64) -> Self {
+        IntervalComplex {
+            re: Interval::point(center.re).add(&Interval::new(-radius, radius)),
+            im: Interval::point(center.im).add(&Interval::new(-radius, radius)),
+        }
+    }
+
+    /// Returns the sum of two rectangles.
+    pub fn add(&self, other: &IntervalComplex) -> IntervalComplex {
+        IntervalComplex {
+            re: self.re.add(&other.re),
+            im: self.im.add(&other.im),
+        }
+    }
+
+    /// Returns the difference of two rectangles.
+    pub fn subtract(&self, other: &IntervalComplex) -> IntervalComplex {
+        IntervalComplex {
+            re: self.re.subtract(&other.re),
+            im: self.im.subtract(&other.im),
+        }
+    }
+
+    /// Returns the product of two rectangles.
+    pub fn multiply(&self, other: &IntervalComplex) -> IntervalComplex {
+        IntervalComplex {
+            re: self.re.multiply(&other.re).subtract(&self.im.multiply(&other.im)),
+            im: self.re.multiply(&other.im).add(&self.im.multiply(&other.re)),
+        }
+    }
+
+    /// Returns the square of the rectangle, tighter than `self.multiply(self)`.
+    pub fn square(&self) -> IntervalComplex {
+        IntervalComplex {
+            re: self.re.square().subtract(&self.im.square()),
+            im: self.re.multiply(&self.im).double(),
+        }
+    }
+
+    /// Returns the squared moduli of the complex numbers of the rectangle.
+    pub fn square_norm(&self) -> Interval {
+        self.re.square().add(&self.im.square())
+    }
+
+    /// Returns the centre of the rectangle, rounded.
+    pub fn midpoint(&self) -> Complex {
+        Complex {
+            re: self.re.midpoint(),
+            im: self.im.midpoint(),
+        }
+    }
+
+    /// Returns an upper bound of the larger side of the rectangle.
+    pub fn width(&self) -> f64 {
+        self.re.width().max(self.im.width())
+    }
+
+    /// Tells whether the rectangle lies in the interior of `other`.
+    pub fn is_interior_to(&self, other: &IntervalComplex) -> bool {
+        self.re.is_interior_to(&other.re) && self.im.is_interior_to(&other.im)
+    }
+
+    /// Tells whether every bound is finite, which fails once an enclosure overflows.
+    pub fn is_finite(&self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+}
@@ -1,7 +1,29 @@
+mod big_complex;
+mod big_float;
 mod complex;
+mod double_double;
+mod dual_complex;
+mod float;
+mod interval;
+mod interval_complex;
 mod fraktals;
+mod orbit_trap;
 mod compute;
+mod pixel_record;
+mod pixel_sink;
+mod render_options;
 
+pub use big_complex::*;
+pub use big_float::*;
 pub use compute::*;
+pub use double_double::*;
+pub use dual_complex::*;
+pub use float::*;
+pub use interval::*;
+pub use interval_complex::*;
 pub use fraktals::*;
+pub use orbit_trap::*;
 pub use complex::*;
+pub use pixel_record::*;
+pub use pixel_sink::*;
+pub use render_options::*;
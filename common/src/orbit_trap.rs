@@ -0,0 +1,162 @@
+//! Shapes the orbits of escape-time fractals are measured against, for orbit-trap colouring.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::complex::{Complex, ComplexTrait};
+
+/// A shape of the plane; the closer an orbit comes to it, the more it is "trapped".
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum OrbitTrap {
+    /// A single point.
+    Point {
+        /// Location of the point.
+        center: Complex,
+    },
+    /// An infinite straight line.
+    Line {
+        /// A point of the line.
+        point: Complex,
+        /// Angle of the line with the real axis, in radians.
+        angle: f64,
+    },
+    /// The horizontal and vertical lines crossing at `center`.
+    Cross {
+        /// Crossing point of the lines.
+        center: Complex,
+    },
+    /// A circle.
+    Circle {
+        /// Centre of the circle.
+        center: Complex,
+        /// Radius of the circle.
+        radius: f64,
+    },
+    /// A bitmap laid over a rectangle of the plane.
+    Image(TrapImage),
+}
+
+impl OrbitTrap {
+    /// Returns the distance from `z` to the trap.
+    ///
+    /// For an image it is `1` minus the opacity of the bitmap at `z`, `1` outside of it:
+    /// orbits come the closest to the trap on its opaque texels.
+    pub fn distance(&self, z: &Complex) -> f64 {
+        match self {
+            OrbitTrap::Point { center } => z.subtract(center).square_norm().sqrt(),
+            OrbitTrap::Line { point, angle } => {
+                let offset = z.subtract(point);
+                (offset.im * angle.cos() - offset.re * angle.sin()).abs()
+            }
+            OrbitTrap::Cross { center } => (z.re - center.re).abs().min((z.im - center.im).abs()),
+            OrbitTrap::Circle { center, radius } => (z.subtract(center).square_norm().sqrt() - radius).abs(),
+            OrbitTrap::Image(image) => 1.0 - image.opacity(z),
+        }
+    }
+}
+
+/// A greyscale bitmap covering a rectangle of the plane, used as an orbit trap.
+///
+/// Clones share the texels, so that every band of a fragment can hold the trap without
+/// copying the bitmap. Deserialized images are checked like those of [`TrapImage::new`]:
+///
+/// ```
+/// use common::TrapImage;
+///
+/// let json = r#"{"min":{"re":-1.0,"im":-1.0},"max":{"re":1.0,"im":1.0},"width":2,"height":2,"texels":[0,255,255,0]}"#;
+/// let image: TrapImage = serde_json::from_str(json).unwrap();
+/// assert_eq!(serde_json::to_string(&image).unwrap(), json);
+///
+/// let truncated = json.replace("[0,255,255,0]", "[0,255]");
+/// assert!(serde_json::from_str::<TrapImage>(&truncated).is_err());
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(try_from = "TrapImageFields", into = "TrapImageFields")]
+pub struct TrapImage {
+    min: Complex,
+    max: Complex,
+    width: u32,
+    height: u32,
+    texels: Arc<[u8]>,
+}
+
+/// Serialized form of a [`TrapImage`].
+#[derive(Deserialize, Serialize)]
+struct TrapImageFields {
+    min: Complex,
+    max: Complex,
+    width: u32,
+    height: u32,
+    texels: Vec<u8>,
+}
+
+impl TryFrom<TrapImageFields> for TrapImage {
+    type Error = String;
+
+    fn try_from(fields: TrapImageFields) -> Result<Self, Self::Error> {
+        TrapImage::new(fields.min, fields.max, fields.width, fields.height, fields.texels)
+    }
+}
+
+impl From<TrapImage> for TrapImageFields {
+    fn from(image: TrapImage) -> Self {
+        TrapImageFields {
+            min: image.min,
+            max: image.max,
+            width: image.width,
+            height: image.height,
+            texels: image.texels.to_vec(),
+        }
+    }
+}
+
+impl TrapImage {
+    /// Lays the bitmap `texels`, `width × height` opacities from `0` (transparent) to `255`
+    /// (opaque) given row by row, over the rectangle with opposite corners `min` and
+    /// `max`. The first row lies along `min.im`.
+    ///
+    /// # Errors
+    /// Returns an error if the bitmap is empty or does not hold `width × height` texels.
+    pub fn new(min: Complex, max: Complex, width: u32, height: u32, texels: Vec<u8>) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("empty trap image of {width}x{height} texels"));
+        }
+        if texels.len() != width as usize * height as usize {
+            return Err(format!(
+                "trap image of {width}x{height} texels given {} texels",
+                texels.len()
+            ));
+        }
+        Ok(TrapImage {
+            min,
+            max,
+            width,
+            height,
+            texels: texels.into(),
+        })
+    }
+
+    /// Returns the opacity of the bitmap at `z`, between `0` and `1`, bilinearly
+    /// interpolated between the centres of the texels. It is `0` outside the rectangle.
+    pub fn opacity(&self, z: &Complex) -> f64 {
+        let u = (z.re - self.min.re) / (self.max.re - self.min.re);
+        let v = (z.im - self.min.im) / (self.max.im - self.min.im);
+        if self.width == 0 || self.height == 0 || !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return 0.0;
+        }
+
+        let x = (u * self.width as f64 - 0.5).clamp(0.0, (self.width - 1) as f64);
+        let y = (v * self.height as f64 - 0.5).clamp(0.0, (self.height - 1) as f64);
+        let (i, j) = (x.floor() as u32, y.floor() as u32);
+        let (fx, fy) = (x - i as f64, y - j as f64);
+
+        let texel = |i: u32, j: u32| {
+            let index = j.min(self.height - 1) as usize * self.width as usize + i.min(self.width - 1) as usize;
+            self.texels.get(index).map_or(0.0, |texel| *texel as f64 / 255.0)
+        };
+        let top = texel(i, j) * (1.0 - fx) + texel(i + 1, j) * fx;
+        let bottom = texel(i, j + 1) * (1.0 - fx) + texel(i + 1, j + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
@@ -0,0 +1,58 @@
+//! Typed per-pixel output of the fractals, richer than [`PixelIntensity`].
+
+use crate::complex::Complex;
+use crate::compute::PixelIntensity;
+
+/// Everything a fractal computed for a pixel, in named channels.
+///
+/// Only the iteration count is always present; every other channel is filled by the
+/// fractals and options producing it. The [`PixelIntensity`] sent to the server is a
+/// projection of the record, chosen by each fractal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PixelRecord {
+    /// Number of iterations performed, `max_iteration` for points that neither escaped
+    /// nor converged.
+    pub count: u32,
+    /// Continuous iteration count of escaped points.
+    pub smooth_count: Option<f64>,
+    /// Last value of the orbit, `None` for pixels filled by subdivision without iterating.
+    pub z: Option<Complex>,
+    /// Derivative of the last value of the orbit with respect to the pixel.
+    pub derivative: Option<Complex>,
+    /// Estimated distance from the pixel to the boundary of the set, in the units of the
+    /// plane.
+    pub distance: Option<f64>,
+    /// Index or ID of the root a Newton iteration converged to.
+    pub root: Option<u32>,
+    /// Smallest distance from the orbit to an orbit trap.
+    pub trap_distance: Option<f64>,
+    /// Iteration at which the orbit came the closest to the orbit trap.
+    pub trap_iteration: Option<u32>,
+    /// Orbit average of an averaging colouring, between `0` and `1`.
+    pub average: Option<f64>,
+    /// Period of the attracting cycle detected for interior points.
+    pub period: Option<u32>,
+}
+
+impl PixelRecord {
+    /// Creates a record holding only the iteration count.
+    pub fn new(count: u32) -> PixelRecord {
+        PixelRecord {
+            count,
+            ..PixelRecord::default()
+        }
+    }
+
+    /// Recovers the record of a pixel from its intensity, whose `count` is normalized by
+    /// `max_iteration`. Only the iteration count can be recovered.
+    pub fn from_intensity(intensity: &PixelIntensity, max_iteration: u32) -> PixelRecord {
+        PixelRecord::new((intensity.count as f64 * max_iteration as f64).round() as u32)
+    }
+
+    /// Returns the iteration count normalized by `max_iteration`, the continuous one when
+    /// it was computed, as stored in [`PixelIntensity::count`].
+    pub fn normalized_count(&self, max_iteration: u32) -> f32 {
+        let count = self.smooth_count.map_or(self.count as f64, |count| count.max(0.0));
+        count as f32 / max_iteration as f32
+    }
+}
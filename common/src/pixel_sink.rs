@@ -0,0 +1,69 @@
+//! Destinations the pixels of a fragment are streamed to as they are computed.
+
+use std::io::{self, BufWriter, Write};
+
+use crate::compute::PixelIntensity;
+
+/// Receives the pixels of a fragment in row-major order, a band at a time.
+pub trait PixelSink {
+    /// Appends `pixels` after the pixels written so far.
+    ///
+    /// # Errors
+    /// Returns an error if the pixels cannot be stored or sent.
+    fn write_pixels(&mut self, pixels: &[PixelIntensity]) -> io::Result<()>;
+}
+
+/// Collects the pixels, into a buffer that can be pre-allocated with [`Vec::with_capacity`].
+impl PixelSink for Vec<PixelIntensity> {
+    fn write_pixels(&mut self, pixels: &[PixelIntensity]) -> io::Result<()> {
+        self.extend_from_slice(pixels);
+        Ok(())
+    }
+}
+
+/// Encodes the pixels in the binary layout of fragment results through a buffered writer:
+/// 8 bytes per pixel, `zn` then `count`, both as big-endian `f32`.
+pub struct PixelWriter<W: Write> {
+    writer: BufWriter<W>,
+    written: usize,
+}
+
+impl<W: Write> PixelWriter<W> {
+    /// Creates a writer encoding the pixels into `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+            written: 0,
+        }
+    }
+
+    /// Returns the number of pixels written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Flushes the buffered pixels and returns the underlying writer.
+    ///
+    /// # Errors
+    /// Returns an error if the buffered pixels cannot be written.
+    pub fn finish(self) -> io::Result<W> {
+        self.writer.into_inner().map_err(|e| e.into_error())
+    }
+
+    /// Discards the buffered pixels and returns the underlying writer, so that a stream
+    /// abandoned after an error does not send part of a band.
+    pub fn abandon(self) -> W {
+        self.writer.into_parts().0
+    }
+}
+
+impl<W: Write> PixelSink for PixelWriter<W> {
+    fn write_pixels(&mut self, pixels: &[PixelIntensity]) -> io::Result<()> {
+        for pixel in pixels {
+            self.writer.write_all(&pixel.zn.to_be_bytes())?;
+            self.writer.write_all(&pixel.count.to_be_bytes())?;
+        }
+        self.written += pixels.len();
+        Ok(())
+    }
+}
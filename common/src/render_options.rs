@@ -0,0 +1,195 @@
+//! Optional per-task rendering settings.
+//!
+//! Every field has a default matching the plain escape-time rendering, so tasks
+//! sent without options are computed exactly as before.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::double_double::DoubleDouble;
+use crate::float::Float;
+use crate::orbit_trap::OrbitTrap;
+
+/// Rendering options attached to a fragment task.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct RenderOptions {
+    /// Computes only the borders of rectangles and fills those whose border has a
+    /// uniform iteration count and period (Mariani–Silver subdivision). Filled pixels are
+    /// not iterated and get a `zn` of `0`.
+    #[serde(default)]
+    pub subdivision: bool,
+    /// Stops iterating interior points early, using the cardioid and period-2 bulb tests
+    /// and Brent's cycle detection on the orbit.
+    #[serde(default)]
+    pub interior_detection: bool,
+    /// Stores the period detected for interior points in `zn` instead of the usual value,
+    /// `0` standing for no detected period.
+    #[serde(default)]
+    pub record_period: bool,
+    /// Arithmetic the escape-time fractals iterate with, `f64` when absent. Workers pick
+    /// one from the depth of the view when the task leaves it out. Other fractals always
+    /// use `f64`.
+    #[serde(default)]
+    pub precision: Option<Precision>,
+    /// Raises `max_iteration` from the zoom depth and keeps doubling it while pixels still
+    /// escape beyond it. Counts are then normalized by the effective limit, which the
+    /// worker reports in the `max_iteration` of the fragment result.
+    #[serde(default)]
+    pub adaptive_iterations: bool,
+    /// Supersampling of the pixels, one sample per pixel when absent.
+    #[serde(default)]
+    pub anti_aliasing: Option<AntiAliasing>,
+    /// Moves every pixel by a pseudo-random fraction of a pixel drawn from this seed,
+    /// instead of sampling the corner of the pixel. Jittered anti-aliasing sets it on the
+    /// supersampled grid.
+    #[serde(default)]
+    pub jitter: Option<u64>,
+    /// Classifies each pixel square of the Mandelbrot and Julia sets as provably exterior,
+    /// provably interior or undetermined, using interval arithmetic. `zn` then holds the
+    /// class and `count` the iteration of the proof. Cannot be combined with
+    /// `anti_aliasing` nor `adaptive_iterations`, which refine iteration counts.
+    #[serde(default)]
+    pub certified: bool,
+    /// Stores in `zn` the estimated distance from the Mandelbrot and Julia pixels to the
+    /// boundary of the set, in pixels, `0` for points that did not escape. Takes over
+    /// `record_period`. Rejected for the iterated sin(z), whose orbits are not polynomial.
+    #[serde(default)]
+    pub distance_estimation: bool,
+    /// Stores in `count` the continuous iteration count of the escaped pixels of the
+    /// escape-time fractals, or of the converged pixels of the Newton fractals, normalized
+    /// by `max_iteration`, which removes the colour banding of integer counts.
+    #[serde(default)]
+    pub smooth_iterations: bool,
+    /// Measures the orbits of the Mandelbrot, Julia, Multibrot and sine pixels against
+    /// this shape. `zn` then holds the smallest distance from the orbit to the trap,
+    /// infinite when the orbit escaped before its first iteration, taking over
+    /// `record_period` but not `distance_estimation`.
+    #[serde(default)]
+    pub orbit_trap: Option<OrbitTrap>,
+    /// Averages a colouring term over the orbits of the Mandelbrot, Julia and Multibrot
+    /// pixels, and is rejected for the iterated sin(z). `zn` then holds the average,
+    /// between `0` and `1`, taking over `record_period` but not `distance_estimation` nor
+    /// `orbit_trap`.
+    #[serde(default)]
+    pub orbit_average: Option<OrbitAverage>,
+}
+
+impl RenderOptions {
+    /// Checks that the options can be combined.
+    ///
+    /// ```
+    /// use common::{AntiAliasing, RenderOptions};
+    ///
+    /// let mut options = RenderOptions { certified: true, ..RenderOptions::default() };
+    /// assert!(options.validate().is_ok());
+    /// options.anti_aliasing = Some(AntiAliasing::Grid { samples: 2 });
+    /// assert!(options.validate().is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a description of the first conflict found.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.certified && self.anti_aliasing.is_some() {
+            return Err("certified rendering cannot be anti-aliased".to_string());
+        }
+        if self.certified && self.adaptive_iterations {
+            return Err("certified rendering cannot use adaptive iterations".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Supersampling computing several samples per pixel and averaging them, so that thin
+/// filaments no longer alias.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum AntiAliasing {
+    /// `samples × samples` samples evenly spread over every pixel.
+    Grid {
+        /// Samples per pixel along each axis, at most 16.
+        samples: u8,
+    },
+    /// `samples × samples` samples per pixel, each at a pseudo-random position within its
+    /// cell of the grid (stratified sampling), trading the regular patterns of the grid for noise.
+    Jittered {
+        /// Samples per pixel along each axis, at most 16.
+        samples: u8,
+    },
+    /// `samples × samples` samples evenly spread over the pixels whose iteration count
+    /// differs from one of their four neighbours by more than `threshold` iterations,
+    /// one sample elsewhere.
+    Adaptive {
+        /// Samples per pixel along each axis, at most 16.
+        samples: u8,
+        /// Iteration count difference above which a pixel is supersampled.
+        threshold: u32,
+    },
+}
+
+impl AntiAliasing {
+    /// Number of samples per pixel along each axis.
+    pub fn samples(self) -> u8 {
+        match self {
+            AntiAliasing::Grid { samples }
+            | AntiAliasing::Jittered { samples }
+            | AntiAliasing::Adaptive { samples, .. } => samples,
+        }
+    }
+}
+
+/// Colouring term averaged over the orbits of the polynomial formulas `z = z^d + c`.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum OrbitAverage {
+    /// `1/2 + 1/2 sin(density · arg z)`, drawing stripes along the external rays.
+    Stripe {
+        /// Number of stripes per turn around the set.
+        density: f64,
+    },
+    /// Position of `|z_n|` between the bounds `||z_{n-1}^d| - |c||` and
+    /// `|z_{n-1}^d| + |c|` given by the triangle inequality.
+    TriangleInequality,
+    /// Angle between the last two steps of the orbit, divided by `π`.
+    Curvature,
+}
+
+/// Floating-point arithmetic used to iterate the orbits.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Precision {
+    /// `f32`: enough for overview renders, but no faster than `f64` once batched, so it is
+    /// only used when requested.
+    Single,
+    /// `f64`, the usual arithmetic.
+    #[default]
+    Double,
+    /// [`crate::DoubleDouble`], about 106 bits: zooms about 15 orders of magnitude deeper
+    /// than `f64`, at a few times its cost.
+    DoubleDouble,
+}
+
+impl Precision {
+    /// Every precision, from the least to the most accurate.
+    pub const ALL: [Precision; 3] = [Precision::Single, Precision::Double, Precision::DoubleDouble];
+
+    /// Relative spacing of consecutive values around `1` in this arithmetic.
+    pub fn epsilon(self) -> f64 {
+        match self {
+            Precision::Single => <f32 as Float>::EPSILON,
+            Precision::Double => <f64 as Float>::EPSILON,
+            Precision::DoubleDouble => DoubleDouble::EPSILON,
+        }
+    }
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    /// Parses `single`, `double` or `double-double`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "single" => Ok(Precision::Single),
+            "double" => Ok(Precision::Double),
+            "double-double" => Ok(Precision::DoubleDouble),
+            _ => Err(format!("unknown precision {name:?}, expected single, double or double-double")),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+//! Times an overview of the Mandelbrot set in every arithmetic, which decides the ones
+//! `select_precision` may pick: an arithmetic is only worth its lost accuracy if it is
+//! faster.
+//!
+//! Run with `cargo bench -p fraktals --bench precision`.
+
+use std::time::{Duration, Instant};
+
+use common::{FractalDescriptor, MandelbrotDescriptor, Point, Precision, Range, Resolution, U8Data};
+use fraktals::{Fractal, FractalMandelbrot};
+use networking::FragmentTask;
+
+/// Renders timed for each arithmetic, the fastest of which is kept.
+const RUNS: u32 = 5;
+
+fn main() {
+    let descriptor = FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None });
+    let range = Range { min: Point { x: -2.0, y: -1.25 }, max: Point { x: 0.5, y: 1.25 } };
+    let fractal = FractalMandelbrot::new();
+
+    for interior_detection in [false, true] {
+        for precision in Precision::ALL {
+            let mut task = FragmentTask::new(
+                U8Data { offset: 0, count: 0 },
+                1000,
+                Resolution { nx: 512, ny: 512 },
+                range.clone(),
+                descriptor.clone(),
+            );
+            task.options.precision = Some(precision);
+            task.options.interior_detection = interior_detection;
+
+            let fastest = (0..RUNS)
+                .map(|_| {
+                    let start = Instant::now();
+                    std::hint::black_box(fractal.generate(&task, &descriptor));
+                    start.elapsed()
+                })
+                .min()
+                .unwrap_or(Duration::ZERO);
+            println!(
+                "{:?} precision, interior detection {}: {:.1} ms",
+                precision,
+                interior_detection,
+                fastest.as_secs_f64() * 1e3,
+            );
+        }
+    }
+}
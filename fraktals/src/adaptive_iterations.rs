@@ -0,0 +1,121 @@
+//! Adaptive iteration limit.
+//!
+//! A fixed `max_iteration` under-iterates deep views, leaving black blobs of pixels that
+//! would escape a little later, and wastes time on shallow ones. Here the limit starts
+//! from an estimate based on the zoom depth, then doubles as long as a noticeable share
+//! of the pixels that reached it escape with the higher limit. Only those pixels are
+//! computed again.
+
+use common::{FractalDescriptor, PixelIntensity};
+use networking::FragmentTask;
+use crate::control::{Cancelled, GenerationControl};
+use crate::fraktal::Fractal;
+use crate::grid::PixelGrid;
+use crate::parallel::{generate_record_pixels_parallel, generate_records_parallel};
+
+/// Width of a view showing the whole Mandelbrot set, taken as zoom depth zero.
+const REFERENCE_WIDTH: f64 = 4.0;
+
+/// Iterations added to the initial limit for every halving of the view width.
+const ITERATIONS_PER_OCTAVE: f64 = 64.0;
+
+/// Highest limit reached: the normalized `f32` counts sent to the server cannot tell
+/// iterations apart beyond.
+const MAX_ADAPTIVE_ITERATION: u32 = 1 << 24;
+
+/// The limit stops growing once fewer than this share of the pixels that reached it
+/// escape after it was doubled.
+const GROWTH_FRACTION: f64 = 0.01;
+
+/// Returns the initial iteration limit of `task`: its own `max_iteration`, raised for
+/// views deeper than an overview of the Mandelbrot set.
+pub fn initial_iteration_limit(task: &FragmentTask) -> u32 {
+    let grid = PixelGrid::new(task);
+    let width = (grid.x_step * grid.nx as f64).max(grid.y_step * grid.ny as f64);
+    let octaves = (REFERENCE_WIDTH / width).log2();
+    let estimate = if octaves.is_finite() && octaves > 0.0 {
+        (octaves * ITERATIONS_PER_OCTAVE) as u32
+    } else {
+        0
+    };
+
+    task.max_iteration.max(estimate).clamp(1, MAX_ADAPTIVE_ITERATION)
+}
+
+/// Generates the fragment of `task` with an adaptive iteration limit, using up to
+/// `threads` threads.
+///
+/// Pixels are computed as typed records, so that their exact iteration counts decide which
+/// of them reached the limit, then projected by [`Fractal::project_records`]. Options
+/// replacing the pixels by another classification, such as `certified`, do not apply.
+///
+/// Returns the pixels, whose counts are normalized by the effective limit, and that limit.
+/// Without interior detection, escaping pixels get the same count whatever the limit, so
+/// the pixels are those of a fragment computed with that limit from the start:
+///
+/// ```
+/// use common::{FractalDescriptor, MandelbrotDescriptor, Point, Range, Resolution, U8Data};
+/// use fraktals::{generate_adaptive, Fractal, FractalMandelbrot, GenerationControl};
+/// use networking::FragmentTask;
+///
+/// let mut task = FragmentTask::new(
+///     U8Data { offset: 0, count: 0 },
+///     16,
+///     Resolution { nx: 48, ny: 36 },
+///     Range { min: Point { x: -0.8, y: 0.05 }, max: Point { x: -0.7, y: 0.125 } },
+///     FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None }),
+/// );
+/// let fractal = FractalMandelbrot::new();
+/// let (pixels, limit) = generate_adaptive(&fractal, &task, &task.fractal, 4, &GenerationControl::new()).unwrap();
+/// assert!(limit > task.max_iteration);
+///
+/// task.max_iteration = limit;
+/// let expected = fractal.generate(&task, &task.fractal);
+/// for (pixel, expected) in pixels.iter().zip(&expected) {
+///     assert_eq!((pixel.zn.to_bits(), pixel.count.to_bits()), (expected.zn.to_bits(), expected.count.to_bits()));
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Cancelled`] as soon as `control` stops the generation.
+pub fn generate_adaptive(
+    fractal: &dyn Fractal,
+    task: &FragmentTask,
+    descriptor: &FractalDescriptor,
+    threads: usize,
+    control: &GenerationControl,
+) -> Result<(Vec<PixelIntensity>, u32), Cancelled> {
+    let mut limited_task = task.clone();
+    limited_task.max_iteration = initial_iteration_limit(task);
+
+    let mut records = generate_records_parallel(fractal, &limited_task, descriptor, threads, control)?;
+
+    while limited_task.max_iteration < MAX_ADAPTIVE_ITERATION {
+        let previous_limit = limited_task.max_iteration;
+        let unescaped: Vec<usize> = (0..records.len())
+            .filter(|index| records[*index].count >= previous_limit)
+            .collect();
+        if unescaped.is_empty() {
+            break;
+        }
+
+        limited_task.max_iteration = previous_limit.saturating_mul(2).min(MAX_ADAPTIVE_ITERATION);
+        let recomputed =
+            generate_record_pixels_parallel(fractal, &limited_task, descriptor, &unescaped, threads, control)?;
+
+        let mut escaped = 0;
+        for (index, record) in unescaped.iter().zip(recomputed) {
+            if record.count < limited_task.max_iteration {
+                escaped += 1;
+            }
+            records[*index] = record;
+        }
+        if (escaped as f64) < GROWTH_FRACTION * unescaped.len() as f64 {
+            break;
+        }
+    }
+
+    let pixels = fractal.project_records(&limited_task, descriptor, &records);
+    Ok((pixels, limited_task.max_iteration))
+}
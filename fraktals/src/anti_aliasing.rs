@@ -0,0 +1,144 @@
+//! Supersampling anti-aliasing.
+//!
+//! With one sample per pixel, a thin filament is either hit or missed depending on where
+//! it crosses the pixel, which aliases filament-heavy regions badly. Here pixels are
+//! sampled `N × N` times on a grid `N` times finer than the fragment along each axis,
+//! whose cells subdivide the pixels, and the samples of each pixel are averaged (box
+//! filter). Only the pixels to refine are computed on the finer grid, through
+//! [`Fractal::generate_pixels`].
+
+use common::{AntiAliasing, FractalDescriptor, PixelIntensity, Resolution};
+use networking::FragmentTask;
+use crate::control::{Cancelled, GenerationControl};
+use crate::fraktal::Fractal;
+use crate::parallel::generate_pixels_parallel;
+
+/// Highest number of samples per pixel along each axis.
+const MAX_SAMPLES: usize = 16;
+
+/// Number of samples computed at once: pixels are refined by chunks holding about this
+/// many samples, so that memory use does not grow with the fragment.
+const CHUNK_SAMPLES: usize = 1 << 16;
+
+/// Refines `pixels`, the fragment of `task` computed with one sample per pixel, by
+/// supersampling it as requested by `anti_aliasing`, using up to `threads` threads.
+///
+/// Samples per axis are capped at 16, and so that the finer grid still fits a
+/// [`Resolution`]:
+///
+/// ```
+/// use common::{AntiAliasing, FractalDescriptor, MandelbrotDescriptor, Point, Range, Resolution, U8Data};
+/// use fraktals::{supersample, Fractal, FractalMandelbrot, GenerationControl};
+/// use networking::FragmentTask;
+///
+/// let task = FragmentTask::new(
+///     U8Data { offset: 0, count: 0 },
+///     50,
+///     Resolution { nx: 20, ny: 16 },
+///     Range { min: Point { x: -2.0, y: -1.2 }, max: Point { x: 1.0, y: 1.2 } },
+///     FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None }),
+/// );
+/// let fractal = FractalMandelbrot::new();
+/// let pixels = fractal.generate(&task, &task.fractal);
+/// let refine = |samples| {
+///     let anti_aliasing = AntiAliasing::Grid { samples };
+///     supersample(&fractal, &task, &task.fractal, anti_aliasing, pixels.clone(), 4, &GenerationControl::new()).unwrap()
+/// };
+/// let (capped, maximum) = (refine(255), refine(16));
+/// for (capped, maximum) in capped.iter().zip(&maximum) {
+///     assert_eq!((capped.zn.to_bits(), capped.count.to_bits()), (maximum.zn.to_bits(), maximum.count.to_bits()));
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Cancelled`] as soon as `control` stops the generation.
+pub fn supersample(
+    fractal: &dyn Fractal,
+    task: &FragmentTask,
+    descriptor: &FractalDescriptor,
+    anti_aliasing: AntiAliasing,
+    mut pixels: Vec<PixelIntensity>,
+    threads: usize,
+    control: &GenerationControl,
+) -> Result<Vec<PixelIntensity>, Cancelled> {
+    let nx = task.resolution.nx as usize;
+    let ny = task.resolution.ny as usize;
+    let samples = (anti_aliasing.samples() as usize)
+        .min(MAX_SAMPLES)
+        .min(u16::MAX as usize / nx.max(ny).max(1));
+    if samples <= 1 {
+        return Ok(pixels);
+    }
+
+    let refined: Vec<usize> = match anti_aliasing {
+        AntiAliasing::Adaptive { threshold, .. } => (0..pixels.len())
+            .filter(|index| has_contrast(&pixels, nx, *index, threshold as f32 / task.max_iteration as f32))
+            .collect(),
+        AntiAliasing::Grid { .. } | AntiAliasing::Jittered { .. } => (0..pixels.len()).collect(),
+    };
+
+    let jittered = matches!(anti_aliasing, AntiAliasing::Jittered { .. });
+    let mut fine_task = task.clone();
+    fine_task.resolution = Resolution {
+        nx: (nx * samples) as u16,
+        ny: (ny * samples) as u16,
+    };
+    if jittered {
+        fine_task.options.jitter = Some(jitter_seed(task));
+    }
+
+    // Without jitter, the first sample of a pixel is the pixel itself, kept from `pixels`.
+    let first_sample = if jittered { 0 } else { 1 };
+    let samples_per_pixel = samples * samples - first_sample;
+    let fine_nx = nx * samples;
+    for chunk in refined.chunks((CHUNK_SAMPLES / samples_per_pixel).max(1)) {
+        let indices: Vec<usize> = chunk
+            .iter()
+            .flat_map(|index| {
+                let (i, j) = (index % nx * samples, index / nx * samples);
+                (first_sample..samples * samples)
+                    .map(move |sample| (j + sample / samples) * fine_nx + i + sample % samples)
+            })
+            .collect();
+        let computed = generate_pixels_parallel(fractal, &fine_task, descriptor, &indices, threads, control)?;
+
+        for (index, pixel_samples) in chunk.iter().zip(computed.chunks(samples_per_pixel)) {
+            let kept = (!jittered).then_some(pixels[*index]);
+            pixels[*index] = average(kept.into_iter().chain(pixel_samples.iter().copied()));
+        }
+    }
+    Ok(pixels)
+}
+
+/// Tells whether the normalized count of the pixel at `index` differs from the count of
+/// one of its four neighbours by more than `threshold`.
+fn has_contrast(pixels: &[PixelIntensity], nx: usize, index: usize, threshold: f32) -> bool {
+    let (i, j) = (index % nx, index / nx);
+    let count = pixels[index].count;
+    let neighbours = [
+        (i > 0).then(|| index - 1),
+        (i + 1 < nx).then(|| index + 1),
+        (j > 0).then(|| index - nx),
+        Some(index + nx).filter(|below| *below < pixels.len()),
+    ];
+
+    neighbours
+        .into_iter()
+        .flatten()
+        .any(|neighbour| (pixels[neighbour].count - count).abs() > threshold)
+}
+
+/// Averages the samples of a pixel.
+fn average(samples: impl Iterator<Item = PixelIntensity>) -> PixelIntensity {
+    let (zn, count, n) = samples.fold((0.0, 0.0, 0), |(zn, count, n), sample| {
+        (zn + sample.zn, count + sample.count, n + 1)
+    });
+    PixelIntensity::new(zn / n as f32, count / n as f32)
+}
+
+/// Jitter seed of `task`, derived from its range so that neighbouring fragments do not
+/// repeat the same pattern and a fragment always renders the same way.
+fn jitter_seed(task: &FragmentTask) -> u64 {
+    task.range.min.x.to_bits() ^ task.range.min.y.to_bits().rotate_left(32)
+}
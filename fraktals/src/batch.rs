@@ -0,0 +1,178 @@
+//! Batched escape-time kernels for the polynomial formulas `z = z^d + c`.
+//!
+//! [`LANES`] orbits are stored in structure-of-arrays form and iterated in lockstep,
+//! each lane being frozen by its own escape mask once it diverges or reaches the
+//! iteration limit. The loops are plain array code that the compiler turns into vector
+//! instructions; on x86-64 a copy of the kernel is compiled for AVX2 and picked at
+//! runtime when the CPU supports it, falling back to the baseline build otherwise. NEON is
+//! part of the aarch64 baseline, so the default build already vectorizes with it there.
+//!
+//! Every lane performs exactly the floating-point operations of
+//! [`crate::EscapeTime::escape`] in the same order and arithmetic, `f32` or `f64`, so
+//! results are bit-identical to the scalar path.
+
+use common::{Complex, ComplexTrait, Float};
+use crate::escape_time::{Orbit, PERIODICITY_TOLERANCE_SQUARE};
+
+/// Number of pixels iterated together by the batched kernels.
+pub const LANES: usize = 4;
+
+/// Orbits of [`LANES`] pixels in structure-of-arrays form, in the arithmetic `T`.
+pub struct OrbitBatch<T = f64> {
+    re: [T; LANES],
+    im: [T; LANES],
+    c_re: [T; LANES],
+    c_im: [T; LANES],
+    count: [u32; LANES],
+    saved_re: [T; LANES],
+    saved_im: [T; LANES],
+    steps_since_save: [u32; LANES],
+    save_interval: [u32; LANES],
+    period: [u32; LANES],
+    used: usize,
+}
+
+impl<T: Float> Default for OrbitBatch<T> {
+    fn default() -> Self {
+        let zero = [T::from_f64(0.0); LANES];
+        OrbitBatch {
+            re: zero,
+            im: zero,
+            c_re: zero,
+            c_im: zero,
+            count: [0; LANES],
+            saved_re: zero,
+            saved_im: zero,
+            steps_since_save: [0; LANES],
+            save_interval: [0; LANES],
+            period: [0; LANES],
+            used: 0,
+        }
+    }
+}
+
+impl<T: Float> OrbitBatch<T> {
+    /// Initializes `lane` with the starting point `z0` and the parameter `c`.
+    pub fn set(&mut self, lane: usize, z0: Complex<T>, c: Complex<T>) {
+        self.re[lane] = z0.re;
+        self.im[lane] = z0.im;
+        self.c_re[lane] = c.re;
+        self.c_im[lane] = c.im;
+        self.count[lane] = 0;
+        self.saved_re[lane] = z0.re;
+        self.saved_im[lane] = z0.im;
+        self.steps_since_save[lane] = 0;
+        self.save_interval[lane] = 1;
+        self.period[lane] = 0;
+        self.used = self.used.max(lane + 1);
+    }
+
+    /// Returns the orbit computed for `lane`.
+    pub fn get(&self, lane: usize) -> Orbit<T> {
+        Orbit {
+            z: Complex::new(self.re[lane], self.im[lane]),
+            count: self.count[lane],
+            derivative: None,
+            smooth_count: None,
+            trap: None,
+            average: None,
+            period: (self.period[lane] > 0).then_some(self.period[lane]),
+            filled: false,
+        }
+    }
+
+    /// Iterates `z = z^degree + c` on every lane until each one diverges beyond
+    /// `threshold` (squared radius) or reaches `max_iteration`.
+    ///
+    /// With `detect_periods`, lanes whose orbit is found periodic by Brent's algorithm
+    /// stop early, as in [`crate::EscapeTime::escape`].
+    pub fn escape(&mut self, degree: u32, threshold: f64, max_iteration: u32, detect_periods: bool) {
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: the CPU supports AVX2, checked just above.
+            unsafe { escape_avx2(self, degree, threshold, max_iteration, detect_periods) };
+            return;
+        }
+
+        escape_lanes(self, degree, threshold, max_iteration, detect_periods);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn escape_avx2<T: Float>(
+    batch: &mut OrbitBatch<T>,
+    degree: u32,
+    threshold: f64,
+    max_iteration: u32,
+    detect_periods: bool,
+) {
+    escape_lanes(batch, degree, threshold, max_iteration, detect_periods);
+}
+
+// Lane-indexed loops over several arrays are the shape the auto-vectorizer expects.
+#[allow(clippy::needless_range_loop)]
+#[inline(always)]
+fn escape_lanes<T: Float>(
+    batch: &mut OrbitBatch<T>,
+    degree: u32,
+    threshold: f64,
+    max_iteration: u32,
+    detect_periods: bool,
+) {
+    let threshold = T::from_f64(threshold);
+    let tolerance = T::from_f64(PERIODICITY_TOLERANCE_SQUARE);
+    let mut active = [false; LANES];
+
+    loop {
+        let mut any_active = false;
+        for lane in 0..LANES {
+            let norm = batch.re[lane] * batch.re[lane] + batch.im[lane] * batch.im[lane];
+            active[lane] = lane < batch.used && norm < threshold && batch.count[lane] < max_iteration;
+            any_active |= active[lane];
+        }
+        if !any_active {
+            break;
+        }
+
+        let mut power_re = batch.re;
+        let mut power_im = batch.im;
+        for _ in 1..degree {
+            for lane in 0..LANES {
+                let re = power_re[lane] * batch.re[lane] - power_im[lane] * batch.im[lane];
+                let im = power_re[lane] * batch.im[lane] + power_im[lane] * batch.re[lane];
+                power_re[lane] = re;
+                power_im[lane] = im;
+            }
+        }
+
+        for lane in 0..LANES {
+            if active[lane] {
+                batch.re[lane] = power_re[lane] + batch.c_re[lane];
+                batch.im[lane] = power_im[lane] + batch.c_im[lane];
+                batch.count[lane] += 1;
+            }
+        }
+
+        if detect_periods {
+            for lane in 0..LANES {
+                if !active[lane] {
+                    continue;
+                }
+                batch.steps_since_save[lane] += 1;
+                let re = batch.re[lane] - batch.saved_re[lane];
+                let im = batch.im[lane] - batch.saved_im[lane];
+                if batch.period[lane] == 0 && re * re + im * im < tolerance {
+                    let period = batch.steps_since_save[lane];
+                    batch.period[lane] = period;
+                    batch.count[lane] = max_iteration - (max_iteration - batch.count[lane]) % period;
+                } else if batch.steps_since_save[lane] == batch.save_interval[lane] {
+                    batch.saved_re[lane] = batch.re[lane];
+                    batch.saved_im[lane] = batch.im[lane];
+                    batch.save_interval[lane] *= 2;
+                    batch.steps_since_save[lane] = 0;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Certified rendering of the quadratic Mandelbrot and Julia sets.
+//!
+//! Instead of iterating a single point per pixel, the whole pixel square is iterated as
+//! a rectangle of [`IntervalComplex`] numbers, which contains the orbits of every point of
+//! the pixel. A pixel is then classified as:
+//!
+//! - exterior, once the rectangle lies outside the disk of radius `max(2, |c|)`: beyond
+//!   it every orbit provably escapes;
+//! - interior, once the rectangle lies inside a trapping box `T` mapped into itself by
+//!   `f^p` for every parameter of the pixel, proven by iterating `T` itself: every orbit
+//!   entering `T` stays bounded. The box is placed around the attracting cycle found by
+//!   iterating the centre of the pixel;
+//! - undetermined otherwise, typically near the boundary of the set, where the
+//!   rectangles grow too fast to prove anything.
+//!
+//! Unlike sampled renderings, exterior and interior pixels are mathematically guaranteed.
+
+use std::ops::Range;
+
+use common::{Complex, ComplexTrait, IntervalComplex, PixelIntensity, PixelRecord, Plane};
+use crate::control::GenerationControl;
+use crate::grid::PixelGrid;
+
+/// Longest attracting cycle searched for a trapping box.
+const MAX_PERIOD: usize = 1024;
+
+/// Distance to the starting point below which the orbit of the centre of a pixel is taken
+/// as back to it, relative to the size of the orbit.
+const PERIOD_TOLERANCE: f64 = 1e-6;
+
+/// Newton steps refining a point of the attracting cycle.
+const NEWTON_STEPS: usize = 8;
+
+/// Half-width of the largest trapping box tried, then divided by [`TRAP_SHRINK`] up to
+/// [`TRAP_ATTEMPTS`] times.
+const TRAP_RADIUS: f64 = 0.5;
+
+/// Ratio between the half-widths of two trapping boxes tried one after the other.
+const TRAP_SHRINK: f64 = 2.0;
+
+/// Number of trapping boxes tried per pixel.
+const TRAP_ATTEMPTS: usize = 32;
+
+/// Proven classification of a pixel square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Certainty {
+    /// Every point of the pixel escapes; the whole pixel left the escape disk at `iteration`.
+    Exterior {
+        /// Iteration at which the escape was proven.
+        iteration: u32,
+    },
+    /// Every point of the pixel has a bounded orbit; the whole pixel entered a trapping
+    /// box at `iteration`.
+    Interior {
+        /// Iteration at which the boundedness was proven.
+        iteration: u32,
+    },
+    /// Neither could be proven within the iteration limit.
+    Undetermined,
+}
+
+impl Certainty {
+    /// Value of `zn` marking an undetermined pixel.
+    pub const UNDETERMINED_CODE: f32 = 0.0;
+    /// Value of `zn` marking an exterior pixel.
+    pub const EXTERIOR_CODE: f32 = 1.0;
+    /// Value of `zn` marking an interior pixel.
+    pub const INTERIOR_CODE: f32 = 2.0;
+
+    /// Encodes the classification into the intensity sent to the server: `zn` holds one of
+    /// the codes of [`Certainty`], `count` the iteration of the proof normalized by
+    /// `max_iteration`, `1` for undetermined pixels.
+    pub fn encode(self, max_iteration: u32) -> PixelIntensity {
+        let max_iteration = max_iteration.max(1) as f32;
+        match self {
+            Certainty::Exterior { iteration } => PixelIntensity::new(Self::EXTERIOR_CODE, iteration as f32 / max_iteration),
+            Certainty::Interior { iteration } => PixelIntensity::new(Self::INTERIOR_CODE, iteration as f32 / max_iteration),
+            Certainty::Undetermined => PixelIntensity::new(Self::UNDETERMINED_CODE, 1.0),
+        }
+    }
+
+    /// Returns the iterations spent classifying the pixel: those of the proof, or
+    /// `max_iteration` when nothing could be proven.
+    pub fn iterations(self, max_iteration: u32) -> u32 {
+        match self {
+            Certainty::Exterior { iteration } | Certainty::Interior { iteration } => iteration,
+            Certainty::Undetermined => max_iteration,
+        }
+    }
+
+    /// Encodes `certainties`, completing `rows` rows, after reporting their iterations to
+    /// `control`.
+    pub(crate) fn encode_reported(
+        certainties: &[Certainty],
+        rows: usize,
+        max_iteration: u32,
+        control: &GenerationControl,
+    ) -> Vec<PixelIntensity> {
+        let records: Vec<_> = certainties
+            .iter()
+            .map(|certainty| PixelRecord::new(certainty.iterations(max_iteration)))
+            .collect();
+        control.report_records(rows, &records);
+        certainties.iter().map(|certainty| certainty.encode(max_iteration)).collect()
+    }
+}
+
+/// Certified classification of the pixels of `z^2 + c` in a Mandelbrot-style parameter
+/// plane or a Julia set.
+pub struct CertifiedQuadratic {
+    plane: Plane,
+}
+
+impl CertifiedQuadratic {
+    /// Creates the classifier of the given plane.
+    pub fn new(plane: Plane) -> Self {
+        CertifiedQuadratic { plane }
+    }
+
+    /// Classifies the pixels of the given rows of `grid`, row by row.
+    pub fn classify_rows(&self, grid: &PixelGrid, rows: Range<usize>, max_iteration: u32) -> Vec<Certainty> {
+        rows.flat_map(|j| (0..grid.nx).map(move |i| (i, j)))
+            .map(|(i, j)| self.classify(grid, i, j, max_iteration))
+            .collect()
+    }
+
+    /// Classifies the pixels of `grid` at the given row-major `indices`.
+    pub fn classify_pixels(&self, grid: &PixelGrid, indices: &[usize], max_iteration: u32) -> Vec<Certainty> {
+        indices
+            .iter()
+            .map(|index| self.classify(grid, index % grid.nx, index / grid.nx, max_iteration))
+            .collect()
+    }
+
+    /// Classifies the square spanning pixel `(i, j)` of `grid` up to its neighbour
+    /// `(i + 1, j + 1)`, both taken at their corners whatever the jitter of the grid.
+    ///
+    /// ```
+    /// use common::{FractalDescriptor, MandelbrotDescriptor, Plane, Point, Range, Resolution, U8Data};
+    /// use fraktals::{Certainty, CertifiedQuadratic, PixelGrid};
+    /// use networking::FragmentTask;
+    ///
+    /// let classify = |x: f64, y: f64, jitter: Option<u64>| {
+    ///     let half_width = 1e-3;
+    ///     let mut task = FragmentTask::new(
+    ///         U8Data { offset: 0, count: 0 },
+    ///         1000,
+    ///         Resolution { nx: 1, ny: 1 },
+    ///         Range { min: Point { x: x - half_width, y: y - half_width }, max: Point { x: x + half_width, y: y + half_width } },
+    ///         FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None }),
+    ///     );
+    ///     task.options.jitter = jitter;
+    ///     CertifiedQuadratic::new(Plane::Parameter { z0: None }).classify(&PixelGrid::new(&task), 0, 0, task.max_iteration)
+    /// };
+    ///
+    /// for jitter in [None, Some(7)] {
+    ///     assert!(matches!(classify(0.0, 0.0, jitter), Certainty::Interior { .. }));
+    ///     assert!(matches!(classify(1.0, 0.0, jitter), Certainty::Exterior { .. }));
+    ///     // The cusp of the main cardioid, where interior and exterior points meet.
+    ///     assert_eq!(classify(0.25, 0.0, jitter), Certainty::Undetermined);
+    /// }
+    /// ```
+    pub fn classify(&self, grid: &PixelGrid, i: usize, j: usize, max_iteration: u32) -> Certainty {
+        let cell = IntervalComplex::from_corners(grid.corner(i, j), grid.corner(i + 1, j + 1));
+        let (z0, c) = match self.plane {
+            Plane::Parameter { z0 } => (IntervalComplex::point(z0.unwrap_or(Complex::new(0.0, 0.0))), cell),
+            Plane::Dynamical { c } => (cell, IntervalComplex::point(c)),
+        };
+        classify_orbit(z0, c, max_iteration)
+    }
+}
+
+/// Classifies the orbits of every `z0` of the rectangle `z0` under `z^2 + c`, for every
+/// `c` of the rectangle `c`.
+fn classify_orbit(z0: IntervalComplex, c: IntervalComplex, max_iteration: u32) -> Certainty {
+    let escape_radius_square = c.square_norm().hi.max(4.0);
+    let escape_diameter = 2.0 * escape_radius_square.sqrt();
+    let trap = find_trap(z0.midpoint(), &c, max_iteration);
+
+    let mut z = z0;
+    for iteration in 0..=max_iteration {
+        if z.square_norm().lo > escape_radius_square {
+            return Certainty::Exterior { iteration };
+        }
+        if trap.is_some_and(|trap| z.is_interior_to(&trap)) {
+            return Certainty::Interior { iteration };
+        }
+        if !z.is_finite() || z.width() > escape_diameter {
+            break;
+        }
+        z = z.square().add(&c);
+    }
+    Certainty::Undetermined
+}
+
+/// Looks for a box around the attracting cycle reached from `start` with the centre of
+/// `c`, and proves that `f^p`, with `f(z) = z^2 + c` and `p` the period of the cycle,
+/// maps it into its interior for every parameter of `c`.
+fn find_trap(start: Complex, c: &IntervalComplex, max_iteration: u32) -> Option<IntervalComplex> {
+    let center = c.midpoint();
+    let step = |z: Complex| z.square().add(&center);
+
+    let mut z = start;
+    for _ in 0..max_iteration {
+        z = step(z);
+        if z.square_norm() > 4.0f64.max(center.square_norm()) {
+            return None;
+        }
+    }
+
+    let reference = z;
+    let tolerance = PERIOD_TOLERANCE * reference.square_norm().sqrt().max(1.0);
+    let period = (1..=MAX_PERIOD).find(|_| {
+        z = step(z);
+        z.subtract(&reference).square_norm().sqrt() < tolerance
+    })?;
+
+    // Newton's method on f^p(w) - w, with the derivative of f^p carried along the cycle
+    let mut cycle_point = reference;
+    for _ in 0..NEWTON_STEPS {
+        let (mut w, mut derivative) = (cycle_point, Complex::new(1.0, 0.0));
+        for _ in 0..period {
+            derivative = derivative.multiply(&w).multiply(&Complex::new(2.0, 0.0));
+            w = step(w);
+        }
+        let correction = w.subtract(&cycle_point).divide(derivative.subtract(&Complex::new(1.0, 0.0)));
+        if !(correction.re.is_finite() && correction.im.is_finite()) {
+            break;
+        }
+        cycle_point = cycle_point.subtract(&correction);
+    }
+
+    (0..TRAP_ATTEMPTS)
+        .map(|attempt| TRAP_RADIUS / TRAP_SHRINK.powi(attempt as i32))
+        .map(|radius| IntervalComplex::around(cycle_point, radius))
+        .find(|trap| {
+            let mut image = *trap;
+            for _ in 0..period {
+                image = image.square().add(c);
+                if !image.is_finite() {
+                    return false;
+                }
+            }
+            image.is_interior_to(trap)
+        })
+}
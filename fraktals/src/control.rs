@@ -0,0 +1,198 @@
+//! Cancellation and progress reporting of a generation.
+//!
+//! A [`GenerationControl`] is checked between bands of rows or pixels, so a generation
+//! can be stopped from another thread or once a deadline has passed, and observed while
+//! it runs. Without a token, a deadline or a sink, the checks cost one branch per band.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use common::PixelRecord;
+
+/// Shared flag stopping the generations it was given to. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every generation using this token or one of its clones.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Tells whether [`CancellationToken::cancel`] was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Receives the progress of a generation, possibly from several threads at once.
+pub trait ProgressSink: Sync {
+    /// Called each time a band is computed, with the number of rows it completed and the
+    /// iterations spent on it. Bands of scattered pixels, recomputed by refinement passes,
+    /// complete no row.
+    fn progress(&self, rows: usize, iterations: u64);
+}
+
+/// A [`ProgressSink`] adding up the progress, to be read while the generation runs.
+///
+/// Iterations are added up from the exact counts of the pixel records, even past the
+/// 2^24 iterations the normalized `f32` counts of the intensities can tell apart:
+///
+/// ```
+/// use common::{FractalDescriptor, MandelbrotDescriptor, Point, Range, Resolution, U8Data};
+/// use fraktals::{generate_parallel, Fractal, FractalMandelbrot, GenerationControl, ProgressCounter};
+/// use networking::FragmentTask;
+///
+/// let mut task = FragmentTask::new(
+///     U8Data { offset: 0, count: 0 },
+///     100_000_007,
+///     Resolution { nx: 16, ny: 12 },
+///     Range { min: Point { x: -0.5, y: -0.3 }, max: Point { x: 0.0, y: 0.3 } },
+///     FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None }),
+/// );
+/// // Interior points, stopped early on their attracting cycle.
+/// task.options.interior_detection = true;
+/// let fractal = FractalMandelbrot::new();
+/// let expected: u64 = fractal.generate_records(&task, &task.fractal).iter().map(|record| record.count as u64).sum();
+///
+/// for threads in [1, 4] {
+///     let counter = ProgressCounter::new();
+///     let control = GenerationControl::new().with_progress(&counter);
+///     generate_parallel(&fractal, &task, &task.fractal, threads, &control).unwrap();
+///     assert_eq!((counter.rows(), counter.iterations()), (12, expected), "{threads} threads");
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ProgressCounter {
+    rows: AtomicUsize,
+    iterations: AtomicU64,
+}
+
+impl ProgressCounter {
+    /// Creates a counter with no progress.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of rows completed so far.
+    pub fn rows(&self) -> usize {
+        self.rows.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of iterations spent so far.
+    pub fn iterations(&self) -> u64 {
+        self.iterations.load(Ordering::Relaxed)
+    }
+}
+
+impl ProgressSink for ProgressCounter {
+    fn progress(&self, rows: usize, iterations: u64) {
+        self.rows.fetch_add(rows, Ordering::Relaxed);
+        self.iterations.fetch_add(iterations, Ordering::Relaxed);
+    }
+}
+
+/// Error returned by a generation stopped by its [`GenerationControl`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fractal generation cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+impl Cancelled {
+    /// Tells whether `error` was converted from [`Cancelled`].
+    pub fn is_cause_of(error: &io::Error) -> bool {
+        error.get_ref().is_some_and(|inner| inner.is::<Cancelled>())
+    }
+}
+
+/// Lets streamed generations, which fail with I/O errors, report a cancellation as an
+/// [`io::ErrorKind::Interrupted`] error.
+impl From<Cancelled> for io::Error {
+    fn from(cancelled: Cancelled) -> Self {
+        io::Error::new(io::ErrorKind::Interrupted, cancelled)
+    }
+}
+
+/// Cancellation token, deadline and progress sink of a generation, all optional.
+#[derive(Clone, Copy, Default)]
+pub struct GenerationControl<'a> {
+    /// Stops the generation once cancelled.
+    pub cancellation: Option<&'a CancellationToken>,
+    /// Stops the generation once passed.
+    pub deadline: Option<Instant>,
+    /// Receives the progress of the generation.
+    pub progress: Option<&'a dyn ProgressSink>,
+}
+
+impl<'a> GenerationControl<'a> {
+    /// Creates a control that never stops the generation and discards its progress.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops the generation when `token` is cancelled.
+    pub fn with_cancellation(mut self, token: &'a CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Stops the generation once `deadline` has passed.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Reports the progress of the generation to `sink`.
+    pub fn with_progress(mut self, sink: &'a dyn ProgressSink) -> Self {
+        self.progress = Some(sink);
+        self
+    }
+
+    /// Tells whether the generation can neither be stopped nor observed.
+    pub fn is_passive(&self) -> bool {
+        self.cancellation.is_none() && self.deadline.is_none() && self.progress.is_none()
+    }
+
+    /// Tells whether the generation can be stopped, by a token or a deadline, so that it
+    /// must be checked often enough to stop soon.
+    pub fn can_stop(&self) -> bool {
+        self.cancellation.is_some() || self.deadline.is_some()
+    }
+
+    /// Returns [`Cancelled`] when the generation must stop.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        let cancelled = self.cancellation.is_some_and(CancellationToken::is_cancelled);
+        let expired = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if cancelled || expired {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reports the band `records`, completing `rows` rows, as computed, with their exact
+    /// iteration counts.
+    pub fn report_records(&self, rows: usize, records: &[PixelRecord]) {
+        let Some(sink) = self.progress else {
+            return;
+        };
+        sink.progress(rows, records.iter().map(|record| record.count as u64).sum());
+    }
+}
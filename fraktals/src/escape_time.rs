@@ -0,0 +1,745 @@
+//! Shared escape-time machinery for formulas of the form `z = f(z, c)`.
+//!
+//! A formula only describes one iteration step; [`EscapeTime`] combines it with a
+//! [`Plane`] to decide whether pixels give the parameter `c` or the starting point `z0`.
+//! Formulas are generic over the [`Float`] arithmetic, which is picked per task, and
+//! more generally over the [`ComplexTrait`] type they iterate: given
+//! [`common::DualComplex`] numbers, they also yield the derivatives of the orbit.
+
+use std::ops::Range;
+
+use common::{Complex, ComplexTrait, DoubleDouble, DualComplex, Float, OrbitAverage, OrbitTrap, PixelRecord, Plane, Precision};
+use crate::batch::{OrbitBatch, LANES};
+use crate::grid::PixelGrid;
+use crate::orbit_average::OrbitAverager;
+use crate::subdivision::generate_subdivided;
+
+/// An iteration formula `z_{n+1} = f(z_n, c)` rendered by escape time.
+pub trait EscapeTimeFormula {
+    /// Applies one iteration of the formula.
+    fn iterate<Z: ComplexTrait + Clone>(&self, z: &Z, c: &Z) -> Z;
+
+    /// Squared radius beyond which an orbit is considered divergent.
+    fn divergence_threshold_square(&self) -> f64;
+
+    /// Critical point of the formula, used as the starting point in the parameter plane.
+    fn critical_point(&self) -> Complex;
+
+    /// Degree `d` when the formula is the polynomial `z^d + c`, which lets
+    /// [`EscapeTime::escape_all`] run the batched kernels of [`crate::batch`].
+    fn polynomial_degree(&self) -> Option<u32> {
+        None
+    }
+
+    /// Period of the attracting cycle of the parameter `c` when it can be proven
+    /// analytically for orbits starting at the critical point, without iterating.
+    fn analytic_period<T: Float>(&self, _c: &Complex<T>) -> Option<u32> {
+        None
+    }
+
+    /// Point of the attracting cycle found by [`EscapeTimeFormula::analytic_period`] that
+    /// the orbit of the critical point approaches after `iteration` iterations.
+    fn analytic_cycle_point<T: Float>(&self, _c: &Complex<T>, _period: u32, _iteration: u32) -> Option<Complex<T>> {
+        None
+    }
+}
+
+/// The quadratic family `z = z^2 + c` of the Mandelbrot and Julia sets.
+pub struct QuadraticFormula {
+    /// Squared divergence radius.
+    pub divergence_threshold_square: f64,
+}
+
+impl EscapeTimeFormula for QuadraticFormula {
+    fn iterate<Z: ComplexTrait + Clone>(&self, z: &Z, c: &Z) -> Z {
+        z.multiply(z).add(c)
+    }
+
+    fn divergence_threshold_square(&self) -> f64 {
+        self.divergence_threshold_square
+    }
+
+    fn critical_point(&self) -> Complex {
+        Complex::new(0.0, 0.0)
+    }
+
+    fn polynomial_degree(&self) -> Option<u32> {
+        Some(2)
+    }
+
+    fn analytic_period<T: Float>(&self, c: &Complex<T>) -> Option<u32> {
+        let quarter = T::from_f64(0.25);
+
+        // Main cardioid.
+        let x = c.re - quarter;
+        let q = x * x + c.im * c.im;
+        if q * (q + x) <= quarter * c.im * c.im {
+            return Some(1);
+        }
+
+        // Period-2 bulb, the disk of radius 1/4 centred on -1.
+        let x = c.re + T::from_f64(1.0);
+        if x * x + c.im * c.im <= T::from_f64(0.0625) {
+            return Some(2);
+        }
+
+        None
+    }
+
+    fn analytic_cycle_point<T: Float>(&self, c: &Complex<T>, period: u32, iteration: u32) -> Option<Complex<T>> {
+        let (one, half, four) = (T::from_f64(1.0), T::from_f64(0.5), T::from_f64(4.0));
+        match period {
+            // Attracting fixed point (1 - √(1 - 4c)) / 2 of the main cardioid.
+            1 => {
+                let root = principal_sqrt(Complex { re: one - four * c.re, im: -(four * c.im) });
+                Some(Complex { re: (one - root.re) * half, im: -root.im * half })
+            }
+            // 2-cycle (-1 ± √(-3 - 4c)) / 2 of the period-2 bulb: the critical point lies in
+            // the basin of the `+` point, which even iterations tend to.
+            2 => {
+                let root = principal_sqrt(Complex { re: -T::from_f64(3.0) - four * c.re, im: -(four * c.im) });
+                let root = if iteration.is_multiple_of(2) { root } else { Complex { re: -root.re, im: -root.im } };
+                Some(Complex { re: (root.re - one) * half, im: root.im * half })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the square root of `z` with a non-negative real part.
+fn principal_sqrt<T: Float>(z: Complex<T>) -> Complex<T> {
+    let (zero, half) = (T::from_f64(0.0), T::from_f64(0.5));
+    let modulus = (z.re * z.re + z.im * z.im).sqrt();
+    let clamp = |value: T| if value < zero { zero } else { value };
+    let re = clamp((modulus + z.re) * half).sqrt();
+    let im = clamp((modulus - z.re) * half).sqrt();
+    Complex { re, im: if z.im < zero { -im } else { im } }
+}
+
+/// The Multibrot family `z = z^d + c`.
+pub struct MultibrotFormula {
+    /// Power `d` of the polynomial.
+    pub exponent: u32,
+}
+
+impl EscapeTimeFormula for MultibrotFormula {
+    fn iterate<Z: ComplexTrait + Clone>(&self, z: &Z, c: &Z) -> Z {
+        let mut power = z.clone();
+        for _ in 1..self.exponent {
+            power = power.multiply(z);
+        }
+        power.add(c)
+    }
+
+    fn divergence_threshold_square(&self) -> f64 {
+        4.0
+    }
+
+    fn critical_point(&self) -> Complex {
+        Complex::new(0.0, 0.0)
+    }
+
+    fn polynomial_degree(&self) -> Option<u32> {
+        Some(self.exponent)
+    }
+}
+
+/// The sine family `z = sin(z) * c`.
+pub struct SinZFormula {}
+
+impl EscapeTimeFormula for SinZFormula {
+    fn iterate<Z: ComplexTrait + Clone>(&self, z: &Z, c: &Z) -> Z {
+        z.sine().multiply(c)
+    }
+
+    fn divergence_threshold_square(&self) -> f64 {
+        50.0
+    }
+
+    fn critical_point(&self) -> Complex {
+        Complex::new(std::f64::consts::FRAC_PI_2, 0.0)
+    }
+}
+
+/// Orbits closer than this (squared distance) to a previously saved point are
+/// considered periodic.
+pub(crate) const PERIODICITY_TOLERANCE_SQUARE: f64 = 1e-24;
+
+/// Squared radius escaped orbits keep iterating to when their derivative is tracked: far
+/// beyond the divergence threshold, distance estimates become accurate.
+pub(crate) const LARGE_BAILOUT_SQUARE: f64 = 1e12;
+
+/// Iterations an escaped orbit may take to reach [`LARGE_BAILOUT_SQUARE`], which takes
+/// only a handful of them for polynomial formulas.
+const MAX_BAILOUT_ITERATIONS: u32 = 64;
+
+/// Keeps iterating an escaped orbit until it leaves [`LARGE_BAILOUT_SQUARE`], for at most
+/// [`MAX_BAILOUT_ITERATIONS`] iterations, passing every new value to `visit`, and returns
+/// the number of iterations performed. An iteration overflowing the arithmetic is dropped,
+/// leaving `z` at its last finite value.
+pub(crate) fn iterate_to_large_bailout<F, Z>(formula: &F, z: &mut Z, c: &Z, mut visit: impl FnMut(&Z)) -> u32
+where
+    F: EscapeTimeFormula,
+    Z: ComplexTrait + Clone,
+    Z::Real: Float,
+{
+    let large_bailout = Z::Real::from_f64(LARGE_BAILOUT_SQUARE);
+    let mut iterations = 0;
+    while iterations < MAX_BAILOUT_ITERATIONS && z.square_norm() < large_bailout {
+        let next = formula.iterate(z, c);
+        if !next.square_norm().to_f64().is_finite() {
+            break;
+        }
+        *z = next;
+        visit(z);
+        iterations += 1;
+    }
+    iterations
+}
+
+/// Returns the continuous iteration count `n + 1 − ln(ln|z|) / ln(d)` of an orbit found at
+/// `z` after `n` iterations, for a formula growing like `|z|^d` once the orbit diverges.
+///
+/// Unlike `n`, it varies continuously from pixel to pixel, provided `z` lies far beyond
+/// the divergence radius: see [`LARGE_BAILOUT_SQUARE`].
+pub fn smooth_iteration_count(iterations: u32, z: Complex, degree: f64) -> f64 {
+    let log_modulus = z.square_norm().ln() / 2.0;
+    iterations as f64 + 1.0 - log_modulus.ln() / degree.ln()
+}
+
+/// Returns the continuous iteration count of an orbit found at `z` after `n` iterations,
+/// coming from `previous`, for formulas without a degree: the growth
+/// `d = ln|z| / ln|previous|` of the last iteration stands for it, and the count
+/// `n + 1 − ln(ln|z| / ln R) / ln(d)` is measured from the divergence radius `R`. Returns
+/// `n` when the last iteration did not grow the orbit.
+fn local_smooth_iteration_count(iterations: u32, z: Complex, previous: Complex, threshold_square: f64) -> f64 {
+    let log_modulus = z.square_norm().ln();
+    let degree = log_modulus / previous.square_norm().ln();
+    let fraction = (log_modulus / threshold_square.ln()).ln() / degree.ln();
+    if degree > 1.0 && fraction.is_finite() {
+        iterations as f64 + 1.0 - fraction
+    } else {
+        iterations as f64
+    }
+}
+
+/// Closest approach of an orbit to an orbit trap.
+#[derive(Debug, Clone, Copy)]
+pub struct TrapHit {
+    /// Smallest distance from the orbit to the trap.
+    pub distance: f64,
+    /// Iteration at which the orbit reached it.
+    pub iteration: u32,
+}
+
+/// Result of iterating the orbit of a pixel, in the arithmetic `T`.
+#[derive(Debug, Clone, Copy)]
+pub struct Orbit<T = f64> {
+    /// Last value of the orbit.
+    pub z: Complex<T>,
+    /// Number of iterations performed, `max_iteration` for points found to be interior.
+    pub count: u32,
+    /// Period of the attracting cycle, when interior detection found one.
+    pub period: Option<u32>,
+    /// Derivative of the last value with respect to the pixel, when distance estimation
+    /// tracked it.
+    pub derivative: Option<Complex<T>>,
+    /// Continuous iteration count of escaped orbits, when smooth iterations were requested.
+    pub smooth_count: Option<f64>,
+    /// Closest approach of the orbit to the orbit trap, when one was given.
+    pub trap: Option<TrapHit>,
+    /// Orbit average of the averaging colouring, when one was requested.
+    pub average: Option<f64>,
+    /// Whether the orbit was filled in by subdivision from a pixel of the border of its
+    /// rectangle rather than iterated: only its count and period hold for the pixel.
+    pub filled: bool,
+}
+
+impl<T: Float> Orbit<T> {
+    /// Returns the orbit with its last value rounded to `f64`.
+    pub fn to_f64(&self) -> Orbit {
+        Orbit {
+            z: self.z.to_f64(),
+            count: self.count,
+            period: self.period,
+            derivative: self.derivative.map(|derivative| derivative.to_f64()),
+            smooth_count: self.smooth_count,
+            trap: self.trap,
+            average: self.average,
+            filled: self.filled,
+        }
+    }
+
+    /// Returns the estimated distance `|z| ln|z| / |dz|` from the pixel to the boundary
+    /// of the set, for escaped orbits whose derivative was tracked.
+    pub fn exterior_distance(&self, max_iteration: u32) -> Option<f64> {
+        if self.count >= max_iteration {
+            return None;
+        }
+        let modulus = self.z.square_norm().to_f64().sqrt();
+        let derivative = self.derivative?.square_norm().to_f64().sqrt();
+        Some(modulus * modulus.ln() / derivative)
+    }
+}
+
+impl Orbit {
+    /// Returns the channels of the orbit as the record of its pixel.
+    pub fn record(&self, max_iteration: u32) -> PixelRecord {
+        PixelRecord {
+            count: self.count,
+            smooth_count: self.smooth_count,
+            z: (!self.filled).then_some(self.z),
+            derivative: self.derivative,
+            distance: self.exterior_distance(max_iteration),
+            trap_distance: self.trap.map(|trap| trap.distance),
+            trap_iteration: self.trap.map(|trap| trap.iteration),
+            average: self.average,
+            period: self.period,
+            ..PixelRecord::default()
+        }
+    }
+}
+
+/// An escape-time formula drawn in a given plane.
+pub struct EscapeTime<F: EscapeTimeFormula> {
+    formula: F,
+    plane: Plane,
+    interior_detection: bool,
+    subdivision: bool,
+    precision: Precision,
+    distance_estimation: bool,
+    smooth_iterations: bool,
+    orbit_trap: Option<OrbitTrap>,
+    orbit_average: Option<OrbitAverage>,
+}
+
+impl<F: EscapeTimeFormula> EscapeTime<F> {
+    /// Combines `formula` with the plane the pixels are mapped to.
+    pub fn new(formula: F, plane: Plane) -> Self {
+        EscapeTime {
+            formula,
+            plane,
+            interior_detection: false,
+            subdivision: false,
+            precision: Precision::Double,
+            distance_estimation: false,
+            smooth_iterations: false,
+            orbit_trap: None,
+            orbit_average: None,
+        }
+    }
+
+    /// Enables the early exit of interior points, through the analytic tests of
+    /// [`EscapeTimeFormula::analytic_period`] and Brent's cycle detection on the orbit.
+    pub fn with_interior_detection(mut self, interior_detection: bool) -> Self {
+        self.interior_detection = interior_detection;
+        self
+    }
+
+    /// Enables Mariani–Silver subdivision in [`EscapeTime::escape_rows`].
+    pub fn with_subdivision(mut self, subdivision: bool) -> Self {
+        self.subdivision = subdivision;
+        self
+    }
+
+    /// Selects the arithmetic [`EscapeTime::escape_rows`] iterates with.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Tracks the derivative of the orbits with respect to the pixel, for
+    /// [`Orbit::exterior_distance`]. Orbits are then iterated one by one, in `f64` at
+    /// least since the derivative quickly overflows `f32`, and without subdivision.
+    ///
+    /// The orbits themselves are iterated as without the derivative, cycle detection included:
+    ///
+    /// ```
+    /// use common::{Complex, ComplexTrait, Plane};
+    /// use fraktals::{EscapeTime, QuadraticFormula};
+    ///
+    /// let escape_time = || {
+    ///     EscapeTime::new(QuadraticFormula { divergence_threshold_square: 4.0 }, Plane::Parameter { z0: None })
+    ///         .with_interior_detection(true)
+    ///         .with_smooth_iterations(true)
+    /// };
+    /// let (plain, derived) = (escape_time(), escape_time().with_distance_estimation(true));
+    /// for c in [(0.3, 0.5), (-0.12, 0.75), (-1.31, 0.0), (-0.75, 0.1)] {
+    ///     let c = Complex::new(c.0, c.1);
+    ///     let (expected, orbit) = (plain.escape(c, 500), derived.escape(c, 500));
+    ///     assert_eq!((orbit.count, orbit.period, orbit.smooth_count), (expected.count, expected.period, expected.smooth_count));
+    ///     assert!(orbit.derivative.is_some());
+    /// }
+    /// ```
+    pub fn with_distance_estimation(mut self, distance_estimation: bool) -> Self {
+        self.distance_estimation = distance_estimation;
+        self
+    }
+
+    /// Computes [`Orbit::smooth_count`] for escaped orbits. Those of polynomial formulas
+    /// keep iterating up to [`LARGE_BAILOUT_SQUARE`] for it. Subdivision no longer applies,
+    /// since filled rectangles would bring the bands back.
+    pub fn with_smooth_iterations(mut self, smooth_iterations: bool) -> Self {
+        self.smooth_iterations = smooth_iterations;
+        self
+    }
+
+    /// Measures the orbits against `orbit_trap`, for [`Orbit::trap`]. Orbits are then
+    /// iterated one by one and without subdivision, and interior points are no longer
+    /// detected analytically, since the closest approach depends on the whole orbit.
+    /// Cloning a trap is cheap: image traps share their bitmap.
+    pub fn with_orbit_trap(mut self, orbit_trap: Option<OrbitTrap>) -> Self {
+        self.orbit_trap = orbit_trap;
+        self
+    }
+
+    /// Computes [`Orbit::average`] with the given averaging colouring, for polynomial
+    /// formulas only. Like orbit traps, it needs whole orbits, iterated one by one.
+    pub fn with_orbit_average(mut self, orbit_average: Option<OrbitAverage>) -> Self {
+        self.orbit_average = orbit_average;
+        self
+    }
+
+    /// Tells whether the orbits must be iterated in full, one by one.
+    fn needs_whole_orbit(&self) -> bool {
+        self.orbit_trap.is_some() || self.orbit_average.is_some()
+    }
+
+    /// Tells whether [`EscapeTime::escape_rows`] subdivides the rows.
+    fn subdivides(&self) -> bool {
+        self.subdivision && !self.distance_estimation && !self.smooth_iterations && !self.needs_whole_orbit()
+    }
+
+    /// Starts averaging the orbit from `z0` with parameter `c`, when an averaging
+    /// colouring applies to the formula.
+    fn averager<T: Float>(&self, z0: &Complex<T>, c: &Complex<T>) -> Option<OrbitAverager> {
+        self.formula.polynomial_degree()?;
+        Some(OrbitAverager::new(self.orbit_average?, z0.to_f64(), c.to_f64()))
+    }
+
+    /// Returns the average of an orbit found at `z` with parameter `c`. Escaped orbits
+    /// keep being averaged up to [`LARGE_BAILOUT_SQUARE`], and their last two averages
+    /// are blended.
+    fn average<T: Float>(&self, mut averager: OrbitAverager, z: Complex<T>, c: &Complex<T>, escaped: bool) -> Option<f64> {
+        let degree = self.formula.polynomial_degree()?;
+        if !escaped {
+            return averager.average();
+        }
+        let mut z = z;
+        iterate_to_large_bailout(&self.formula, &mut z, c, |z| averager.push(z.to_f64()));
+        let bailout_square = LARGE_BAILOUT_SQUARE.max(self.formula.divergence_threshold_square());
+        averager.escaped_average(&z.to_f64(), bailout_square, degree as f64)
+    }
+
+    /// Records the distance from `z`, reached at `iteration`, to the orbit trap in `hit`
+    /// when it is the smallest one so far.
+    fn measure_trap<T: Float>(&self, hit: &mut Option<TrapHit>, z: &Complex<T>, iteration: u32) {
+        let Some(orbit_trap) = &self.orbit_trap else {
+            return;
+        };
+        let distance = orbit_trap.distance(&z.to_f64());
+        if hit.is_none_or(|hit| distance < hit.distance) {
+            *hit = Some(TrapHit { distance, iteration });
+        }
+    }
+
+    /// Returns the continuous iteration count of an orbit that escaped at `z` after
+    /// `iterations` iterations, coming from `previous`.
+    fn smooth_count<T: Float>(&self, z: Complex<T>, previous: Complex<T>, c: &Complex<T>, iterations: u32) -> f64 {
+        match self.formula.polynomial_degree() {
+            Some(degree) => {
+                let mut z = z;
+                let bailout_iterations = iterate_to_large_bailout(&self.formula, &mut z, c, |_| {});
+                smooth_iteration_count(iterations + bailout_iterations, z.to_f64(), degree as f64)
+            }
+            None => local_smooth_iteration_count(
+                iterations,
+                z.to_f64(),
+                previous.to_f64(),
+                self.formula.divergence_threshold_square(),
+            ),
+        }
+    }
+
+    /// Returns the starting point `z0` and the parameter `c` of the orbit of `pixel`.
+    pub fn orbit_seed<T: Float>(&self, pixel: Complex<T>) -> (Complex<T>, Complex<T>) {
+        match self.plane {
+            Plane::Parameter { z0 } => (
+                Complex::from_f64(z0.unwrap_or_else(|| self.formula.critical_point())),
+                pixel,
+            ),
+            Plane::Dynamical { c } => (pixel, Complex::from_f64(c)),
+        }
+    }
+
+    /// Returns the orbit of `pixel` when it is known to be interior without iterating.
+    fn analytic_interior<T: Float>(&self, pixel: Complex<T>, max_iteration: u32) -> Option<Orbit<T>> {
+        if !self.interior_detection || self.needs_whole_orbit() {
+            return None;
+        }
+        let Plane::Parameter { z0 } = self.plane else {
+            return None;
+        };
+        let critical_point = self.formula.critical_point();
+        let z0 = z0.unwrap_or(critical_point);
+        if z0.re != critical_point.re || z0.im != critical_point.im {
+            return None;
+        }
+
+        let period = self.formula.analytic_period(&pixel)?;
+        let z = self.formula.analytic_cycle_point(&pixel, period, max_iteration)?;
+        Some(Orbit {
+            z,
+            count: max_iteration,
+            period: Some(period),
+            derivative: None,
+            smooth_count: None,
+            trap: None,
+            average: None,
+            filled: false,
+        })
+    }
+
+    /// Iterates the orbit of `pixel` until it diverges or reaches `max_iteration`.
+    ///
+    /// With interior detection enabled, the orbit also stops as soon as it is found to
+    /// be periodic, and is then reported with `max_iteration` iterations, on the point of
+    /// its cycle that iterating up to `max_iteration` approaches:
+    ///
+    /// ```
+    /// use common::{Complex, ComplexTrait, Plane};
+    /// use fraktals::{EscapeTime, QuadraticFormula};
+    ///
+    /// let formula = || QuadraticFormula { divergence_threshold_square: 4.0 };
+    /// let iterated = EscapeTime::new(formula(), Plane::Parameter { z0: None });
+    /// let detected = EscapeTime::new(formula(), Plane::Parameter { z0: None }).with_interior_detection(true);
+    ///
+    /// // Main cardioid and period-2 bulb, found analytically, then periods 3 and 4 found by
+    /// // cycle detection.
+    /// for (c, period) in [((-0.2, 0.1), 1), ((0.1, -0.3), 1), ((-1.0, 0.0), 2), ((-1.1, 0.05), 2), ((-0.12, 0.75), 3), ((-1.31, 0.0), 4)] {
+    ///     let c = Complex::new(c.0, c.1);
+    ///     for max_iteration in [2000, 2001] {
+    ///         let expected = iterated.escape(c, max_iteration);
+    ///         let orbit = detected.escape(c, max_iteration);
+    ///         assert_eq!((orbit.count, orbit.period), (max_iteration, Some(period)));
+    ///         assert!(orbit.z.subtract(&expected.z).square_norm() < 1e-16, "{c:?}: {:?} for {:?}", orbit.z, expected.z);
+    ///     }
+    /// }
+    /// ```
+    pub fn escape<T: Float>(&self, pixel: Complex<T>, max_iteration: u32) -> Orbit<T> {
+        if let Some(orbit) = self.analytic_interior(pixel, max_iteration) {
+            return orbit;
+        }
+        if self.distance_estimation {
+            return self.escape_with_derivative(pixel, max_iteration);
+        }
+
+        let (z0, c) = self.orbit_seed(pixel);
+        self.iterate_orbit(z0, &c, |z| *z, max_iteration).1
+    }
+
+    /// Iterates `zn` with parameter `c` until it diverges or reaches `max_iteration`, as
+    /// [`EscapeTime::escape`] does, and returns its last value along with the orbit, with
+    /// no derivative. `value` gives the point of the orbit an iterated number stands for:
+    /// the number itself, or the value of a dual number.
+    fn iterate_orbit<T, Z>(&self, mut zn: Z, c: &Z, value: impl Fn(&Z) -> Complex<T>, max_iteration: u32) -> (Z, Orbit<T>)
+    where
+        T: Float,
+        Z: ComplexTrait<Real = T> + Clone,
+    {
+        let threshold = T::from_f64(self.formula.divergence_threshold_square());
+        let tolerance = T::from_f64(PERIODICITY_TOLERANCE_SQUARE);
+        let mut count = 0;
+
+        // Brent's cycle detection: compare against a point saved at powers of two.
+        let mut saved = value(&zn);
+        let mut steps_since_save = 0;
+        let mut save_interval = 1;
+        let mut period = None;
+        let mut previous = value(&zn);
+        let mut trap = None;
+        let mut averager = self.averager(&value(&zn), &value(c));
+
+        while zn.square_norm() < threshold && count < max_iteration {
+            previous = value(&zn);
+            zn = self.formula.iterate(&zn, c);
+            count += 1;
+            let z = value(&zn);
+            self.measure_trap(&mut trap, &z, count);
+            if let Some(averager) = &mut averager {
+                averager.push(z.to_f64());
+            }
+
+            if self.interior_detection {
+                steps_since_save += 1;
+                if period.is_none() && z.subtract(&saved).square_norm() < tolerance {
+                    // Finish on the point of the cycle iterating up to `max_iteration`
+                    // would reach, at most `period - 1` iterations away.
+                    period = Some(steps_since_save);
+                    count = max_iteration - (max_iteration - count) % steps_since_save;
+                } else if steps_since_save == save_interval {
+                    saved = z;
+                    save_interval *= 2;
+                    steps_since_save = 0;
+                }
+            }
+        }
+
+        let (z, c) = (value(&zn), value(c));
+        let smooth_count = (self.smooth_iterations && count < max_iteration)
+            .then(|| self.smooth_count(z, previous, &c, count));
+        let average = averager.and_then(|averager| self.average(averager, z, &c, count < max_iteration));
+        (zn, Orbit { z, count, period, derivative: None, smooth_count, trap, average, filled: false })
+    }
+
+    /// Iterates the orbit of `pixel` like [`EscapeTime::escape`] on dual numbers, which
+    /// also yields the derivative of the orbit with respect to the pixel: `dz/dc` in the
+    /// parameter plane, `dz/dz0` in the dynamical plane. Escaped orbits then keep
+    /// iterating up to [`LARGE_BAILOUT_SQUARE`] without counting.
+    fn escape_with_derivative<T: Float>(&self, pixel: Complex<T>, max_iteration: u32) -> Orbit<T> {
+        let (z0, c) = self.orbit_seed(pixel);
+        let (zn, c) = match self.plane {
+            Plane::Parameter { .. } => (DualComplex::constant(z0), DualComplex::variable(c)),
+            Plane::Dynamical { .. } => (DualComplex::variable(z0), DualComplex::constant(c)),
+        };
+
+        let (mut zn, mut orbit) = self.iterate_orbit(zn, &c, |z| z.value, max_iteration);
+        if orbit.count < max_iteration {
+            iterate_to_large_bailout(&self.formula, &mut zn, &c, |_| {});
+        }
+        orbit.z = zn.value;
+        orbit.derivative = Some(zn.first);
+        orbit
+    }
+
+    /// Iterates the orbits of several pixels, as [`EscapeTime::escape`] would one by one.
+    ///
+    /// Polynomial formulas are iterated [`crate::batch::LANES`] pixels at a time, in `f32`
+    /// as in `f64`, with bit-identical results:
+    ///
+    /// ```
+    /// use common::{Complex, ComplexTrait, Plane};
+    /// use fraktals::{EscapeTime, EscapeTimeFormula, MultibrotFormula, QuadraticFormula};
+    ///
+    /// fn check<F: EscapeTimeFormula>(formula: F, interior_detection: bool) {
+    ///     let escape_time = EscapeTime::new(formula, Plane::Parameter { z0: None })
+    ///         .with_interior_detection(interior_detection);
+    ///     let pixels: Vec<Complex> = (0..997)
+    ///         .map(|k| Complex::new(-1.8 + 2.4 * (k % 71) as f64 / 71.0, -1.2 + 2.4 * (k / 71) as f64 / 14.0))
+    ///         .collect();
+    ///     let batched = escape_time.escape_all(&pixels, 300);
+    ///     for (pixel, orbit) in pixels.iter().zip(&batched) {
+    ///         let scalar = escape_time.escape(*pixel, 300);
+    ///         assert_eq!((orbit.count, orbit.period), (scalar.count, scalar.period));
+    ///         assert_eq!(orbit.z.re.to_bits(), scalar.z.re.to_bits());
+    ///         assert_eq!(orbit.z.im.to_bits(), scalar.z.im.to_bits());
+    ///     }
+    ///
+    ///     let single: Vec<Complex<f32>> = pixels.iter().map(|pixel| Complex::from_f64(*pixel)).collect();
+    ///     for (pixel, orbit) in single.iter().zip(escape_time.escape_all(&single, 300)) {
+    ///         let scalar = escape_time.escape(*pixel, 300);
+    ///         assert_eq!((orbit.count, orbit.period), (scalar.count, scalar.period));
+    ///         assert_eq!(orbit.z.re.to_bits(), scalar.z.re.to_bits());
+    ///         assert_eq!(orbit.z.im.to_bits(), scalar.z.im.to_bits());
+    ///     }
+    /// }
+    ///
+    /// for interior_detection in [false, true] {
+    ///     check(QuadraticFormula { divergence_threshold_square: 4.0 }, interior_detection);
+    ///     check(MultibrotFormula { exponent: 3 }, interior_detection);
+    ///     check(MultibrotFormula { exponent: 5 }, interior_detection);
+    /// }
+    /// ```
+    pub fn escape_all<T: Float>(&self, pixels: &[Complex<T>], max_iteration: u32) -> Vec<Orbit<T>> {
+        let degree = match self.formula.polynomial_degree() {
+            Some(degree) if !self.distance_estimation && !self.needs_whole_orbit() => degree,
+            _ => return pixels.iter().map(|pixel| self.escape(*pixel, max_iteration)).collect(),
+        };
+
+        let mut orbits: Vec<Option<Orbit<T>>> = pixels
+            .iter()
+            .map(|pixel| self.analytic_interior(*pixel, max_iteration))
+            .collect();
+        let pending: Vec<usize> = (0..pixels.len()).filter(|index| orbits[*index].is_none()).collect();
+
+        let threshold = self.formula.divergence_threshold_square();
+        for chunk in pending.chunks(LANES) {
+            let mut batch = OrbitBatch::default();
+            for (lane, index) in chunk.iter().enumerate() {
+                let (z0, c) = self.orbit_seed(pixels[*index]);
+                batch.set(lane, z0, c);
+            }
+            batch.escape(degree, threshold, max_iteration, self.interior_detection);
+            for (lane, index) in chunk.iter().enumerate() {
+                let mut orbit = batch.get(lane);
+                if self.smooth_iterations && orbit.count < max_iteration {
+                    let (_, c) = self.orbit_seed(pixels[*index]);
+                    orbit.smooth_count = Some(self.smooth_count(orbit.z, orbit.z, &c, orbit.count));
+                }
+                orbits[*index] = Some(orbit);
+            }
+        }
+
+        orbits.into_iter().flatten().collect()
+    }
+
+    /// Iterates the pixels of the given rows of `grid`, with the arithmetic and the
+    /// subdivision selected on this driver, and returns their orbits row by row.
+    pub fn escape_rows(&self, grid: &PixelGrid, rows: Range<usize>, max_iteration: u32) -> Vec<Orbit> {
+        match (self.arithmetic(), self.subdivides()) {
+            (Precision::Single, false) => self.escape_all_at::<f32>(grid.row_points_at(rows), max_iteration),
+            (Precision::Double, false) => self.escape_all(&grid.row_points(rows).collect::<Vec<_>>(), max_iteration),
+            (Precision::Single, true) => self.escape_rows_at::<f32>(grid, rows, max_iteration),
+            (Precision::Double, true) => self.escape_rows_at::<f64>(grid, rows, max_iteration),
+            (Precision::DoubleDouble, _) => self.escape_rows_at::<DoubleDouble>(grid, rows, max_iteration),
+        }
+    }
+
+    /// Returns the arithmetic the orbits are iterated with: the selected one, except for
+    /// distance estimation which needs `f64` at least.
+    fn arithmetic(&self) -> Precision {
+        match self.precision {
+            Precision::Single if self.distance_estimation => Precision::Double,
+            precision => precision,
+        }
+    }
+
+    /// Iterates the given pixels in the arithmetic `T`, batched when the formula allows it,
+    /// and returns their orbits rounded to `f64`.
+    fn escape_all_at<T: Float>(&self, pixels: impl Iterator<Item = Complex<T>>, max_iteration: u32) -> Vec<Orbit> {
+        let pixels: Vec<Complex<T>> = pixels.collect();
+        self.escape_all(&pixels, max_iteration).iter().map(Orbit::to_f64).collect()
+    }
+
+    /// Iterates the pixels of the given rows one by one in the arithmetic `T`.
+    fn escape_rows_at<T: Float>(&self, grid: &PixelGrid, rows: Range<usize>, max_iteration: u32) -> Vec<Orbit> {
+        let escape = |pixel: Complex<T>| self.escape(pixel, max_iteration).to_f64();
+        if self.subdivides() {
+            return generate_subdivided(grid, rows, escape);
+        }
+        grid.row_points_at(rows).map(escape).collect()
+    }
+
+    /// Iterates the pixels of `grid` at the given row-major indices, with the arithmetic
+    /// selected on this driver. Subdivision does not apply to scattered pixels.
+    pub fn escape_pixels(&self, grid: &PixelGrid, indices: &[usize], max_iteration: u32) -> Vec<Orbit> {
+        match self.arithmetic() {
+            Precision::Single => {
+                self.escape_all_at::<f32>(indices.iter().map(|index| grid.index_point_at(*index)), max_iteration)
+            }
+            Precision::Double => {
+                let pixels: Vec<Complex> = indices.iter().map(|index| grid.index_point(*index)).collect();
+                self.escape_all(&pixels, max_iteration)
+            }
+            Precision::DoubleDouble => self.escape_pixels_at::<DoubleDouble>(grid, indices, max_iteration),
+        }
+    }
+
+    /// Iterates the pixels at the given indices one by one in the arithmetic `T`.
+    fn escape_pixels_at<T: Float>(&self, grid: &PixelGrid, indices: &[usize], max_iteration: u32) -> Vec<Orbit> {
+        indices
+            .iter()
+            .map(|index| self.escape(grid.index_point_at::<T>(*index), max_iteration).to_f64())
+            .collect()
+    }
+}
@@ -0,0 +1,279 @@
+//! External rays and equipotential curves of the Mandelbrot set.
+//!
+//! Both curves are traced by Newton's method on the Böttcher coordinate `φ(c)`,
+//! approximated by `z_n(c)^(1 / 2^(n - 1))` once `|z_n(c)|` is large, the orbit starting
+//! at `z_1 = c`. Rays are traced inward from a large escape radius, doubling the angle
+//! each time one more iteration is needed.
+
+use common::{Complex, ComplexTrait, PixelIntensity, Point};
+use networking::FragmentTask;
+use crate::grid::PixelGrid;
+
+/// A rational external angle `numerator / denominator`, measured in turns.
+#[derive(Debug, Clone, Copy)]
+pub struct ExternalAngle {
+    numerator: u64,
+    denominator: u64,
+}
+
+impl ExternalAngle {
+    /// Creates the angle `numerator / denominator`, reduced modulo one turn.
+    ///
+    /// # Panics
+    /// Panics if `denominator` is zero.
+    pub fn new(numerator: u64, denominator: u64) -> Self {
+        assert!(denominator > 0, "External angle denominator must be positive");
+        ExternalAngle {
+            numerator: numerator % denominator,
+            denominator,
+        }
+    }
+
+    /// Returns the angle doubled modulo one turn, computed exactly.
+    pub fn doubled(&self) -> Self {
+        let numerator = (self.numerator as u128 * 2) % self.denominator as u128;
+        ExternalAngle {
+            numerator: numerator as u64,
+            denominator: self.denominator,
+        }
+    }
+
+    /// Returns the angle in radians.
+    pub fn radians(&self) -> f64 {
+        std::f64::consts::TAU * self.numerator as f64 / self.denominator as f64
+    }
+}
+
+/// Parameters of the Newton tracing of rays and equipotentials.
+#[derive(Debug, Clone, Copy)]
+pub struct RayTracing {
+    /// Number of points traced each time the angle is doubled.
+    pub sharpness: u32,
+    /// Number of angle doublings; deeper rays get closer to the boundary of the set.
+    pub depth: u32,
+    /// Radius `|z_n|` at which the Böttcher coordinate is approximated.
+    pub escape_radius: f64,
+    /// Maximum number of Newton steps for each traced point.
+    pub newton_steps: u32,
+}
+
+impl Default for RayTracing {
+    fn default() -> Self {
+        RayTracing {
+            sharpness: 8,
+            depth: 64,
+            escape_radius: 65536.0,
+            newton_steps: 64,
+        }
+    }
+}
+
+/// Solves `z_n(c) = target` by Newton's method starting from `c`.
+fn solve_iterate(mut c: Complex, iterations: u32, target: &Complex, newton_steps: u32) -> Complex {
+    for _ in 0..newton_steps {
+        let mut z = Complex::new(0.0, 0.0);
+        let mut dc = Complex::new(0.0, 0.0);
+        for _ in 0..iterations {
+            dc = z.multiply(&dc).multiply(&Complex::new(2.0, 0.0)).add(&Complex::new(1.0, 0.0));
+            z = z.multiply(&z).add(&c);
+        }
+
+        let c_next = c.subtract(&z.subtract(target).divide(dc));
+        if !c_next.square_norm().is_finite() {
+            break;
+        }
+        let step = c_next.subtract(&c).square_norm();
+        c = c_next;
+        if step <= 1e-30 * c.square_norm().max(1e-30) {
+            break;
+        }
+    }
+    c
+}
+
+/// Inward tracer following a ray point by point.
+struct RayTracer {
+    angle: ExternalAngle,
+    settings: RayTracing,
+    c: Complex,
+    doubling: u32,
+    substep: u32,
+}
+
+impl RayTracer {
+    fn new(angle: ExternalAngle, settings: RayTracing) -> Self {
+        let start = Complex::new(settings.escape_radius, 0.0)
+            .multiply(&Complex::new(0.0, angle.radians()).exponential());
+        RayTracer {
+            angle,
+            settings,
+            c: start,
+            doubling: 0,
+            substep: 0,
+        }
+    }
+
+    /// Radius `|z_n|` targeted by the current step.
+    fn iterate_radius(&self) -> f64 {
+        let fraction = (self.substep as f64 + 0.5) / self.settings.sharpness as f64;
+        self.settings.escape_radius.powf(0.5f64.powf(fraction))
+    }
+
+    /// Radius `|φ(c)|` of the Böttcher coordinate targeted by the current step.
+    fn bottcher_radius(&self) -> f64 {
+        self.iterate_radius().powf(0.5f64.powi(self.doubling as i32))
+    }
+
+    fn step(&mut self) -> Complex {
+        let target = Complex::new(self.iterate_radius(), 0.0)
+            .multiply(&Complex::new(0.0, self.angle.radians()).exponential());
+        self.c = solve_iterate(self.c, self.doubling + 1, &target, self.settings.newton_steps);
+
+        self.substep += 1;
+        if self.substep >= self.settings.sharpness {
+            self.substep = 0;
+            self.doubling += 1;
+            self.angle = self.angle.doubled();
+        }
+        self.c
+    }
+}
+
+/// Traces the external ray of the Mandelbrot set at `angle`, from the escape radius
+/// towards the boundary of the set.
+///
+/// Returns the ray as a polyline in plane coordinates.
+pub fn external_ray(angle: ExternalAngle, settings: &RayTracing) -> Vec<Point> {
+    let mut tracer = RayTracer::new(angle, *settings);
+    let mut polyline = vec![Point { x: tracer.c.re, y: tracer.c.im }];
+
+    while tracer.doubling < settings.depth {
+        let c = tracer.step();
+        if !c.square_norm().is_finite() {
+            break;
+        }
+        polyline.push(Point { x: c.re, y: c.im });
+    }
+
+    polyline
+}
+
+/// Traces the equipotential curve of the Mandelbrot set where the Green function
+/// `ln|φ(c)|` equals `potential`.
+///
+/// The curve is sampled at `samples` evenly spaced external angles and returned as
+/// a closed polyline in plane coordinates.
+///
+/// ```
+/// use common::{Complex, ComplexTrait};
+/// use fraktals::{equipotential, RayTracing};
+///
+/// // Green function G(c) = lim ln|z_n| / 2^(n - 1), with z_1 = c.
+/// let green = |c: Complex| {
+///     let (mut z, mut n) = (c, 1);
+///     while z.square_norm() < 1e20 {
+///         z = z.multiply(&z).add(&c);
+///         n += 1;
+///     }
+///     z.square_norm().sqrt().ln() / 2f64.powi(n - 1)
+/// };
+///
+/// for potential in [0.5, 3.0, 5.0] {
+///     let curve = equipotential(potential, 32, &RayTracing::default());
+///     for point in curve.iter().step_by(8) {
+///         let g = green(Complex::new(point.x, point.y));
+///         assert!((g - potential).abs() < 1e-3 * potential, "G = {g} for {potential}");
+///     }
+///
+///     // The curve goes once around the set.
+///     let winding: f64 = curve
+///         .windows(2)
+///         .map(|pair| {
+///             let turn = pair[1].y.atan2(pair[1].x) - pair[0].y.atan2(pair[0].x);
+///             (turn + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI
+///         })
+///         .sum();
+///     assert!((winding - std::f64::consts::TAU).abs() < 1e-6, "winding {winding}");
+/// }
+/// ```
+pub fn equipotential(potential: f64, samples: usize, settings: &RayTracing) -> Vec<Point> {
+    let radius = potential.exp();
+
+    // Follow the ray of angle zero down to the requested potential.
+    let mut tracer = RayTracer::new(ExternalAngle::new(0, 1), *settings);
+    while tracer.bottcher_radius() > radius && tracer.doubling < settings.depth {
+        tracer.step();
+    }
+
+    let mut iterations = 1;
+    while radius.powf(2f64.powi(iterations - 1)) < settings.escape_radius && iterations < settings.depth as i32 {
+        iterations += 1;
+    }
+    let iterate_radius = radius.powf(2f64.powi(iterations - 1));
+    let mut c = solve_iterate(
+        tracer.c,
+        iterations as u32,
+        &Complex::new(iterate_radius, 0.0),
+        settings.newton_steps,
+    );
+
+    // The iterate winds 2^(n - 1) times around the target circle: keep the continuation steps
+    // small enough for Newton's method to stay on the same branch.
+    let samples = samples.max(1);
+    let substeps = ((1usize << (iterations - 1).min(24)) * 16).div_ceil(samples).max(1);
+    let total = samples * substeps;
+
+    let mut polyline = Vec::with_capacity(samples + 1);
+    for step in 0..=total {
+        if step % substeps == 0 {
+            polyline.push(Point { x: c.re, y: c.im });
+        }
+        let turns = (step + 1) as f64 / total as f64 * 2f64.powi(iterations - 1);
+        let target = Complex::new(iterate_radius, 0.0)
+            .multiply(&Complex::new(0.0, std::f64::consts::TAU * turns.fract()).exponential());
+        c = solve_iterate(c, iterations as u32, &target, settings.newton_steps);
+    }
+
+    polyline
+}
+
+/// Rasterizes polylines onto the pixel grid of `task`.
+///
+/// Pixels crossed by a polyline get an intensity of `1.0` in both `zn` and `count`,
+/// every other pixel is `0.0`, so the layer can be blended over a rendered fragment.
+pub fn rasterize_polylines(polylines: &[Vec<Point>], task: &FragmentTask) -> Vec<PixelIntensity> {
+    let grid = PixelGrid::new(task);
+    let mut mask = vec![false; grid.nx * grid.ny];
+
+    let to_pixel = |point: &Point| {
+        (
+            (point.x - grid.origin.re) / grid.x_step,
+            (point.y - grid.origin.im) / grid.y_step,
+        )
+    };
+
+    for polyline in polylines {
+        for segment in polyline.windows(2) {
+            let (x0, y0) = to_pixel(&segment[0]);
+            let (x1, y1) = to_pixel(&segment[1]);
+            if !(x0.is_finite() && y0.is_finite() && x1.is_finite() && y1.is_finite()) {
+                continue;
+            }
+
+            let length = (x1 - x0).abs().max((y1 - y0).abs());
+            let steps = (length.ceil() as usize).clamp(1, 4 * (grid.nx + grid.ny));
+            for step in 0..=steps {
+                let t = step as f64 / steps as f64;
+                let i = (x0 + (x1 - x0) * t).round();
+                let j = (y0 + (y1 - y0) * t).round();
+                if i >= 0.0 && j >= 0.0 && (i as usize) < grid.nx && (j as usize) < grid.ny {
+                    mask[j as usize * grid.nx + i as usize] = true;
+                }
+            }
+        }
+    }
+
+    mask.into_iter()
+        .map(|hit| if hit { PixelIntensity::new(1.0, 1.0) } else { PixelIntensity::new(0.0, 0.0) })
+        .collect()
+}
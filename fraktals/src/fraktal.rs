@@ -1,7 +1,25 @@
 //! Trait definition for generating fractal pixel data based on a given task and descriptor.
 
-use common::{FractalDescriptor, PixelIntensity};
+use std::io;
+use std::ops::Range;
+
+use common::{FractalDescriptor, PixelIntensity, PixelRecord, PixelSink};
 use networking::FragmentTask;
+use crate::control::{Cancelled, GenerationControl};
+
+/// Fewest rows of a band checked by a [`GenerationControl`] or written to a [`PixelSink`].
+pub(crate) const BAND_HEIGHT: usize = 8;
+
+/// Number of pixels computed between two checks of a [`GenerationControl`] able to stop
+/// the generation: enough for bands to keep the rectangles of subdivision and the series
+/// of deep zooms large, few enough to stop soon after a cancellation or the deadline.
+pub(crate) const CONTROLLED_BAND_PIXELS: usize = 1 << 16;
+
+/// Height of the bands of rows of `fragment_task` between two checks of a
+/// [`GenerationControl`] able to stop the generation, or written at once to a [`PixelSink`].
+pub(crate) fn controlled_band_height(fragment_task: &FragmentTask) -> usize {
+    (CONTROLLED_BAND_PIXELS / (fragment_task.resolution.nx as usize).max(1)).max(BAND_HEIGHT)
+}
 
 /// A trait that represents a fractal generator capable of computing pixel intensities
 /// for a given region (fragment) of the fractal space.
@@ -9,16 +27,29 @@ use networking::FragmentTask;
 /// Implementations of this trait should define how the fractal is generated
 /// based on the specific `FractalDescriptor` provided.
 ///
-/// This trait supports generating only a single fragment at a time,
-/// allowing it to be used in parallel or distributed rendering systems.
-pub trait Fractal {
-    /// Generates pixel intensities for the given fragment and fractal descriptor.
+/// Rows of a fragment are independent from each other, which allows a fragment to be
+/// split into row chunks computed in parallel (see [`crate::generate_parallel`]).
+pub trait Fractal: Sync {
+    /// Generates pixel intensities for a band of rows of the given fragment.
     ///
     /// # Arguments
     ///
     /// * `fragment_task` - Metadata describing the portion of the image to compute.
     /// * `descriptor` - A fractal descriptor enum containing all parameters
     ///   needed to generate the desired fractal.
+    /// * `rows` - Indices of the rows to compute, `0` being the row at `range.min.y`.
+    ///
+    /// # Returns
+    ///
+    /// The [`PixelIntensity`] values of the requested rows, row by row.
+    fn generate_rows(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelIntensity>;
+
+    /// Generates pixel intensities for the whole fragment.
     ///
     /// # Returns
     ///
@@ -28,5 +59,187 @@ pub trait Fractal {
         &self,
         fragment_task: &FragmentTask,
         descriptor: &FractalDescriptor,
-    ) -> Vec<PixelIntensity>;
+    ) -> Vec<PixelIntensity> {
+        self.generate_rows(fragment_task, descriptor, 0..fragment_task.resolution.ny as usize)
+    }
+
+    /// Generates the typed records of a band of rows of the given fragment, of which the
+    /// intensities of [`Fractal::generate_rows`] are a projection.
+    ///
+    /// The provided implementation only recovers the iteration counts from the
+    /// intensities, rounded from their normalized `f32` values and thus exact only up to
+    /// about 2^24 iterations; fractals computing more channels override it. Options
+    /// replacing the pixels by another classification, such as `certified`, do not apply
+    /// to records.
+    fn generate_record_rows(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelRecord> {
+        self.generate_rows(fragment_task, descriptor, rows)
+            .iter()
+            .map(|intensity| PixelRecord::from_intensity(intensity, fragment_task.max_iteration))
+            .collect()
+    }
+
+    /// Projects records computed by [`Fractal::generate_record_rows`] or
+    /// [`Fractal::generate_record_pixels`] onto the intensities sent to the server, as
+    /// [`Fractal::generate_rows`] would for the same pixels.
+    ///
+    /// The provided implementation keeps only the normalized iteration count; fractals
+    /// overriding [`Fractal::generate_record_rows`] override it too. Like records, the
+    /// projection ignores options such as `certified`.
+    fn project_records(
+        &self,
+        fragment_task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        records: &[PixelRecord],
+    ) -> Vec<PixelIntensity> {
+        records
+            .iter()
+            .map(|record| PixelIntensity::new(0.0, record.normalized_count(fragment_task.max_iteration)))
+            .collect()
+    }
+
+    /// Generates the typed records of the whole fragment.
+    fn generate_records(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+    ) -> Vec<PixelRecord> {
+        self.generate_record_rows(fragment_task, descriptor, 0..fragment_task.resolution.ny as usize)
+    }
+
+    /// Generates pixel intensities for the whole fragment like [`Fractal::generate`], by
+    /// bands of rows, checking `control` before each band and reporting it once computed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Cancelled`] as soon as `control` stops the generation.
+    fn generate_controlled(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        control: &GenerationControl,
+    ) -> Result<Vec<PixelIntensity>, Cancelled> {
+        let ny = fragment_task.resolution.ny as usize;
+        let band_height = controlled_band_height(fragment_task);
+        let mut pixels = Vec::with_capacity(fragment_task.resolution.nx as usize * ny);
+
+        for start in (0..ny).step_by(band_height) {
+            control.check()?;
+            let end = (start + band_height).min(ny);
+            pixels.extend(self.generate_reported_rows(fragment_task, descriptor, start..end, control));
+        }
+        Ok(pixels)
+    }
+
+    /// Generates a band of rows like [`Fractal::generate_rows`], reporting it to `control`
+    /// once computed with the exact iteration counts of its records.
+    ///
+    /// The provided implementation projects the records of
+    /// [`Fractal::generate_record_rows`]; fractals whose pixels are not the projection of
+    /// their records for some options, such as `certified`, override it.
+    fn generate_reported_rows(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+        control: &GenerationControl,
+    ) -> Vec<PixelIntensity> {
+        let row_count = rows.len();
+        let records = self.generate_record_rows(fragment_task, descriptor, rows);
+        control.report_records(row_count, &records);
+        self.project_records(fragment_task, descriptor, &records)
+    }
+
+    /// Generates the whole fragment like [`Fractal::generate`], writing it to `sink` band
+    /// by band instead of returning it, so that memory use does not grow with the fragment.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of `sink`.
+    fn generate_into(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        sink: &mut dyn PixelSink,
+    ) -> io::Result<()> {
+        let ny = fragment_task.resolution.ny as usize;
+        let band_height = controlled_band_height(fragment_task);
+        for start in (0..ny).step_by(band_height) {
+            let end = (start + band_height).min(ny);
+            sink.write_pixels(&self.generate_rows(fragment_task, descriptor, start..end))?;
+        }
+        Ok(())
+    }
+
+    /// Generates pixel intensities for scattered pixels of the fragment, given by their
+    /// row-major indices, as [`Fractal::generate_rows`] would compute them.
+    ///
+    /// Refinement passes use it to recompute only some pixels. The provided
+    /// implementation computes every row holding a requested pixel, so indices are best
+    /// given in increasing order; fractals able to compute single pixels override it.
+    fn generate_pixels(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelIntensity> {
+        pick_pixels(fragment_task, indices, |j| self.generate_rows(fragment_task, descriptor, j..j + 1))
+    }
+
+    /// Generates scattered pixels like [`Fractal::generate_pixels`], reporting them to
+    /// `control` as [`Fractal::generate_reported_rows`] does, with no row completed.
+    fn generate_reported_pixels(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+        control: &GenerationControl,
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_pixels(fragment_task, descriptor, indices);
+        control.report_records(0, &records);
+        self.project_records(fragment_task, descriptor, &records)
+    }
+
+    /// Generates the typed records of scattered pixels of the fragment, given by their
+    /// row-major indices, as [`Fractal::generate_record_rows`] would compute them.
+    ///
+    /// Like [`Fractal::generate_pixels`], the provided implementation computes every row
+    /// holding a requested pixel.
+    fn generate_record_pixels(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelRecord> {
+        pick_pixels(fragment_task, indices, |j| self.generate_record_rows(fragment_task, descriptor, j..j + 1))
+    }
+}
+
+/// Picks the pixels at `indices` out of the rows computed by `generate_row`, computing
+/// each row once for consecutive indices in the same row.
+fn pick_pixels<T: Copy>(
+    fragment_task: &FragmentTask,
+    indices: &[usize],
+    mut generate_row: impl FnMut(usize) -> Vec<T>,
+) -> Vec<T> {
+    let nx = fragment_task.resolution.nx as usize;
+    let mut row: Option<(usize, Vec<T>)> = None;
+
+    indices
+        .iter()
+        .map(|index| {
+            let j = index / nx;
+            let pixels = match row.take() {
+                Some((computed, pixels)) if computed == j => pixels,
+                _ => generate_row(j),
+            };
+            let pixel = pixels[index % nx];
+            row = Some((j, pixels));
+            pixel
+        })
+        .collect()
 }
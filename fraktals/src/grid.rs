@@ -0,0 +1,123 @@
+//! Mapping between the pixels of a fragment and points of the complex plane.
+
+use std::ops::Range;
+
+use common::{Complex, ComplexTrait, Float};
+use networking::FragmentTask;
+
+/// The pixel grid of a [`FragmentTask`], laid out row by row from `range.min`.
+///
+/// Pixel `(i, j)` is located at `min + (i * x_step, j * y_step)`, so the grid always
+/// contains exactly `nx * ny` points whatever the rounding of the steps. With a jitter
+/// seed, each pixel is moved by its own pseudo-random fraction of a step along both axes.
+pub struct PixelGrid {
+    /// Plane coordinates of the first pixel.
+    pub origin: Complex,
+    /// Low-order parts of the origin, from the `range_low` of the task.
+    pub origin_low: Complex,
+    /// Horizontal distance between two neighbouring pixels.
+    pub x_step: f64,
+    /// Vertical distance between two neighbouring pixels.
+    pub y_step: f64,
+    /// Number of pixels per row.
+    pub nx: usize,
+    /// Number of rows.
+    pub ny: usize,
+    /// Seed of the pixel offsets, from the `jitter` option of the task.
+    pub jitter: Option<u64>,
+}
+
+impl PixelGrid {
+    /// Builds the grid covering the range and resolution of `task`.
+    pub fn new(task: &FragmentTask) -> Self {
+        let x_start = task.range.min.x;
+        let x_end = task.range.max.x;
+        let y_start = task.range.min.y;
+        let y_end = task.range.max.y;
+        let (x_start_low, x_end_low, y_start_low, y_end_low) = task
+            .range_low
+            .as_ref()
+            .map_or((0.0, 0.0, 0.0, 0.0), |low| (low.min.x, low.max.x, low.min.y, low.max.y));
+
+        PixelGrid {
+            origin: Complex::new(x_start, y_start),
+            origin_low: Complex::new(x_start_low, y_start_low),
+            x_step: (((x_start - x_end) + (x_start_low - x_end_low)) / task.resolution.nx as f64).abs(),
+            y_step: (((y_start - y_end) + (y_start_low - y_end_low)) / task.resolution.ny as f64).abs(),
+            nx: task.resolution.nx as usize,
+            ny: task.resolution.ny as usize,
+            jitter: task.options.jitter,
+        }
+    }
+
+    /// Returns the plane coordinates of pixel `(i, j)`.
+    pub fn point(&self, i: usize, j: usize) -> Complex {
+        self.point_at(i, j)
+    }
+
+    /// Returns the plane coordinates of pixel `(i, j)` computed in the arithmetic `T`.
+    ///
+    /// With a type wider than `f64`, the low-order parts of the origin are kept and pixels
+    /// stay distinct even when the steps are far below the `f64` spacing around the origin.
+    pub fn point_at<T: Float>(&self, i: usize, j: usize) -> Complex<T> {
+        let re = T::from_f64(self.origin.re) + T::from_f64(self.origin_low.re);
+        let im = T::from_f64(self.origin.im) + T::from_f64(self.origin_low.im);
+        let (x_offset, y_offset) = self.offset(i, j);
+        Complex::new(
+            re + T::from_f64(i as f64 + x_offset) * T::from_f64(self.x_step),
+            im + T::from_f64(j as f64 + y_offset) * T::from_f64(self.y_step),
+        )
+    }
+
+    /// Returns the plane coordinates of the corner of pixel `(i, j)`, where it lies
+    /// without jitter.
+    pub fn corner(&self, i: usize, j: usize) -> Complex {
+        Complex::new(
+            self.origin.re + self.origin_low.re + i as f64 * self.x_step,
+            self.origin.im + self.origin_low.im + j as f64 * self.y_step,
+        )
+    }
+
+    /// Returns the jitter of pixel `(i, j)` in steps, both in `[0, 1)`.
+    fn offset(&self, i: usize, j: usize) -> (f64, f64) {
+        let Some(seed) = self.jitter else {
+            return (0.0, 0.0);
+        };
+        let hash = split_mix(seed ^ split_mix((i as u64) << 32 | j as u64));
+        let unit = |bits: u64| (bits >> 40) as f64 / (1u64 << 24) as f64;
+        (unit(hash), unit(hash << 24))
+    }
+
+    /// Returns the plane coordinates of the pixel at the row-major `index`.
+    pub fn index_point(&self, index: usize) -> Complex {
+        self.index_point_at(index)
+    }
+
+    /// Returns the plane coordinates of the pixel at the row-major `index` in the arithmetic `T`.
+    pub fn index_point_at<T: Float>(&self, index: usize) -> Complex<T> {
+        self.point_at(index % self.nx, index / self.nx)
+    }
+
+    /// Iterates over every pixel of the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Complex> + '_ {
+        self.row_points(0..self.ny)
+    }
+
+    /// Iterates over the pixels of the given rows, row by row.
+    pub fn row_points(&self, rows: Range<usize>) -> impl Iterator<Item = Complex> + '_ {
+        self.row_points_at(rows)
+    }
+
+    /// Iterates over the pixels of the given rows in the arithmetic `T`, row by row.
+    pub fn row_points_at<T: Float>(&self, rows: Range<usize>) -> impl Iterator<Item = Complex<T>> + '_ {
+        rows.flat_map(move |j| (0..self.nx).map(move |i| self.point_at(i, j)))
+    }
+}
+
+/// SplitMix64 finalizer: scrambles `value` into well-distributed pseudo-random bits.
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -1,6 +1,10 @@
-use common::{Complex, ComplexTrait, FractalDescriptor, PixelIntensity};
+use std::ops::Range;
+
+use common::{Complex, ComplexTrait, FractalDescriptor, PixelIntensity, PixelRecord, Plane};
 use networking::FragmentTask;
+use crate::escape_time::{EscapeTime, SinZFormula};
 use crate::fraktal::Fractal;
+use crate::grid::PixelGrid;
 
 pub struct IteratedSinZ {
     c: Complex,
@@ -10,45 +14,130 @@ impl IteratedSinZ {
     pub fn new(c: Complex) -> Self {
         IteratedSinZ { c }
     }
+
+    /// Checks that the render options of `task` apply to the sine family: distance
+    /// estimates and orbit averages assume a polynomial formula.
+    ///
+    /// ```
+    /// use common::{Complex, ComplexTrait, FractalDescriptor, IteratedSinZDescriptor, OrbitAverage, Point, Range, Resolution, U8Data};
+    /// use fraktals::IteratedSinZ;
+    /// use networking::FragmentTask;
+    ///
+    /// let mut task = FragmentTask::new(
+    ///     U8Data { offset: 0, count: 0 },
+    ///     100,
+    ///     Resolution { nx: 4, ny: 4 },
+    ///     Range { min: Point { x: -2.0, y: -2.0 }, max: Point { x: 2.0, y: 2.0 } },
+    ///     FractalDescriptor::IteratedSinZ(IteratedSinZDescriptor { c: Complex::new(1.0, 0.3), plane: None }),
+    /// );
+    /// task.options.subdivision = true;
+    /// task.options.interior_detection = true;
+    /// assert!(IteratedSinZ::validate(&task).is_ok());
+    ///
+    /// task.options.distance_estimation = true;
+    /// assert!(IteratedSinZ::validate(&task).is_err());
+    /// task.options.distance_estimation = false;
+    /// task.options.orbit_average = Some(OrbitAverage::Stripe { density: 5.0 });
+    /// assert!(IteratedSinZ::validate(&task).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a description of the first unsupported option found.
+    pub fn validate(task: &FragmentTask) -> Result<(), String> {
+        if task.options.distance_estimation {
+            return Err("distance estimation is not supported by the iterated sin(z) fractal".to_string());
+        }
+        if task.options.orbit_average.is_some() {
+            return Err("orbit averages are not supported by the iterated sin(z) fractal".to_string());
+        }
+        Ok(())
+    }
+
+    /// Escape-time driver configured for `task`.
+    fn escape_time(&self, task: &FragmentTask, descriptor: &FractalDescriptor) -> EscapeTime<SinZFormula> {
+        let plane = match descriptor {
+            FractalDescriptor::IteratedSinZ(sin_descriptor) => sin_descriptor.plane,
+            _ => None,
+        };
+        EscapeTime::new(
+            SinZFormula {},
+            plane.unwrap_or(Plane::Dynamical { c: self.c }),
+        )
+        .with_interior_detection(task.options.interior_detection)
+        .with_subdivision(task.options.subdivision)
+        .with_precision(task.options.precision.unwrap_or_default())
+        .with_smooth_iterations(task.options.smooth_iterations)
+        .with_orbit_trap(task.options.orbit_trap.clone())
+    }
+
+    /// Projects the record of a pixel onto the intensity sent to the server.
+    fn encode(task: &FragmentTask, record: &PixelRecord) -> PixelIntensity {
+        let intensity = record.normalized_count(task.max_iteration);
+        let escape_time = if task.options.orbit_trap.is_some() {
+            record.trap_distance.unwrap_or(f64::INFINITY) as f32
+        } else if task.options.record_period {
+            record.period.unwrap_or(0) as f32
+        } else {
+            record.z.map_or(0.0, |z| z.square_norm()) as f32 / 4.0
+        };
+
+        PixelIntensity::new(escape_time, intensity)
+    }
 }
 
 impl Fractal for IteratedSinZ {
-    fn generate(&self, task: &FragmentTask, _descriptor: &FractalDescriptor) -> Vec<PixelIntensity> {
-        let x_start = task.range.min.x;
-        let x_end = task.range.max.x;
-        let y_start = task.range.min.y;
-        let y_end = task.range.max.y;
-
-        let x_step = ((&x_start - &x_end) / task.resolution.nx as f64).abs();
-        let y_step = ((&y_start - &y_end) / task.resolution.ny as f64).abs();
-
-        let mut pixel_intensity_vec: Vec<PixelIntensity> = Vec::new();
-        let max_iteration = task.max_iteration;
-
-        let mut x = x_start;
-        let mut y = y_start;
-
-        while y < y_end {
-            while x < x_end {
-                let pixel_complexe = Complex::new(x, y);
-                let mut zn = pixel_complexe;
-                let mut count = 0;
-
-                while zn.square_norm() < 50.0 && count < max_iteration {
-                    zn = zn.sine().multiply(&self.c);
-                    count += 1;
-                }
-
-                let intensity = count as f32 / max_iteration as f32;
-                let escape_time = zn.square_norm() as f32 / 4.0;
-
-                pixel_intensity_vec.push(PixelIntensity::new(escape_time, intensity));
-                x += x_step;
-            }
-            x = x_start;
-            y += y_step;
-        }
+    fn generate_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_rows(task, descriptor, rows);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn generate_record_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelRecord> {
+        self.escape_time(task, descriptor)
+            .escape_rows(&PixelGrid::new(task), rows, task.max_iteration)
+            .iter()
+            .map(|orbit| orbit.record(task.max_iteration))
+            .collect()
+    }
+
+    fn generate_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_pixels(task, descriptor, indices);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn project_records(
+        &self,
+        task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        records: &[PixelRecord],
+    ) -> Vec<PixelIntensity> {
+        records.iter().map(|record| Self::encode(task, record)).collect()
+    }
 
-        pixel_intensity_vec
+    fn generate_record_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelRecord> {
+        self.escape_time(task, descriptor)
+            .escape_pixels(&PixelGrid::new(task), indices, task.max_iteration)
+            .iter()
+            .map(|orbit| orbit.record(task.max_iteration))
+            .collect()
     }
 }
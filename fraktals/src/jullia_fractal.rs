@@ -1,6 +1,12 @@
-use common::{Complex, ComplexTrait, FractalDescriptor, PixelIntensity};
+use std::ops::Range;
+
+use common::{ComplexTrait, FractalDescriptor, PixelIntensity, PixelRecord, Plane};
 use networking::FragmentTask;
+use crate::certified::{Certainty, CertifiedQuadratic};
+use crate::control::GenerationControl;
+use crate::escape_time::{EscapeTime, QuadraticFormula};
 use crate::fraktal::Fractal;
+use crate::grid::PixelGrid;
 
 pub struct FractalJulia {}
 
@@ -8,52 +14,178 @@ impl FractalJulia {
     pub fn new() -> FractalJulia {
         FractalJulia {}
     }
+
+    /// Escape-time driver configured for `fragment_task`, `None` for another fractal.
+    fn escape_time(
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+    ) -> Option<EscapeTime<QuadraticFormula>> {
+        let FractalDescriptor::Julia(julia_descriptor) = descriptor else {
+            return None;
+        };
+        let escape_time = EscapeTime::new(
+            QuadraticFormula { divergence_threshold_square: julia_descriptor.divergence_threshold_square },
+            julia_descriptor.plane.unwrap_or(Plane::Dynamical { c: julia_descriptor.c }),
+        )
+        .with_interior_detection(fragment_task.options.interior_detection)
+        .with_subdivision(fragment_task.options.subdivision)
+        .with_precision(fragment_task.options.precision.unwrap_or_default())
+        .with_distance_estimation(fragment_task.options.distance_estimation)
+        .with_smooth_iterations(fragment_task.options.smooth_iterations)
+        .with_orbit_trap(fragment_task.options.orbit_trap.clone())
+        .with_orbit_average(fragment_task.options.orbit_average);
+        Some(escape_time)
+    }
+
+    /// Certified classifier of `fragment_task`, `None` unless it asks for certified
+    /// rendering of a Julia set.
+    fn certified(fragment_task: &FragmentTask, descriptor: &FractalDescriptor) -> Option<CertifiedQuadratic> {
+        let FractalDescriptor::Julia(julia_descriptor) = descriptor else {
+            return None;
+        };
+        let plane = julia_descriptor.plane.unwrap_or(Plane::Dynamical { c: julia_descriptor.c });
+        fragment_task.options.certified.then(|| CertifiedQuadratic::new(plane))
+    }
+
+    /// Projects the record of a pixel of `grid` onto the intensity sent to the server.
+    fn encode(fragment_task: &FragmentTask, grid: &PixelGrid, threshold: f64, record: &PixelRecord) -> PixelIntensity {
+        let zn = if fragment_task.options.distance_estimation {
+            let distance = record.distance.unwrap_or(0.0);
+            (distance / grid.x_step.min(grid.y_step)) as f32
+        } else if fragment_task.options.orbit_trap.is_some() {
+            record.trap_distance.unwrap_or(f64::INFINITY) as f32
+        } else if fragment_task.options.orbit_average.is_some() {
+            record.average.unwrap_or(0.0) as f32
+        } else if fragment_task.options.record_period {
+            record.period.unwrap_or(0) as f32
+        } else {
+            record.z.map_or(0.0, |z| z.argument()) as f32 / threshold as f32
+        };
+        let count = record.normalized_count(fragment_task.max_iteration);
+        PixelIntensity::new(zn, count)
+    }
+}
+
+impl Default for FractalJulia {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Fractal for FractalJulia {
-    fn generate(
+    fn generate_rows(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelIntensity> {
+        if let Some(certified) = Self::certified(fragment_task, descriptor) {
+            return certified
+                .classify_rows(&PixelGrid::new(fragment_task), rows, fragment_task.max_iteration)
+                .into_iter()
+                .map(|certainty| certainty.encode(fragment_task.max_iteration))
+                .collect();
+        }
+        let records = self.generate_record_rows(fragment_task, descriptor, rows);
+        self.project_records(fragment_task, descriptor, &records)
+    }
+
+    fn generate_record_rows(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelRecord> {
+        let Some(escape_time) = Self::escape_time(fragment_task, descriptor) else {
+            return Vec::new();
+        };
+        escape_time
+            .escape_rows(&PixelGrid::new(fragment_task), rows, fragment_task.max_iteration)
+            .iter()
+            .map(|orbit| orbit.record(fragment_task.max_iteration))
+            .collect()
+    }
+
+    fn generate_reported_rows(
         &self,
         fragment_task: &FragmentTask,
         descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+        control: &GenerationControl,
     ) -> Vec<PixelIntensity> {
-        let x_start = fragment_task.range.min.x;
-        let x_end = fragment_task.range.max.x;
-
-        let y_start = fragment_task.range.min.y;
-        let y_end = fragment_task.range.max.y;
-
-        let x_step = ((x_start - x_end) / fragment_task.resolution.nx as f64).abs();
-        let y_step = ((y_start - y_end) / fragment_task.resolution.ny as f64).abs();
-
-        let mut pixel_intensity_vec: Vec<PixelIntensity> = Vec::new();
-
-        let mut x = x_start;
-        let mut y = y_start;
-
-        while y < y_end {
-            while x < x_end {
-                let pixel_complexe = Complex { re: x, im: y };
-                if let FractalDescriptor::Julia(julia_descriptor) = descriptor {
-                    let mut zn: Complex = pixel_complexe;
-                    let mut count = 0;
-
-                    while count < fragment_task.max_iteration
-                        && zn.square_norm() < julia_descriptor.divergence_threshold_square
-                    {
-                        zn = zn.multiply(&zn).add(&julia_descriptor.c);
-                        count += 1;
-                    }
-                    let zn = zn.argument() as f32 / julia_descriptor.divergence_threshold_square as f32;
-                    let count = count as f32 / fragment_task.max_iteration as f32;
-                    pixel_intensity_vec.push(PixelIntensity::new(zn, count));
-                }
-
-                x += x_step;
-            }
-            x = x_start;
-            y += y_step;
+        let row_count = rows.len();
+        if let Some(certified) = Self::certified(fragment_task, descriptor) {
+            let certainties = certified.classify_rows(&PixelGrid::new(fragment_task), rows, fragment_task.max_iteration);
+            return Certainty::encode_reported(&certainties, row_count, fragment_task.max_iteration, control);
         }
+        let records = self.generate_record_rows(fragment_task, descriptor, rows);
+        control.report_records(row_count, &records);
+        self.project_records(fragment_task, descriptor, &records)
+    }
+
+    fn generate_pixels(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelIntensity> {
+        if let Some(certified) = Self::certified(fragment_task, descriptor) {
+            return certified
+                .classify_pixels(&PixelGrid::new(fragment_task), indices, fragment_task.max_iteration)
+                .into_iter()
+                .map(|certainty| certainty.encode(fragment_task.max_iteration))
+                .collect();
+        }
+        let records = self.generate_record_pixels(fragment_task, descriptor, indices);
+        self.project_records(fragment_task, descriptor, &records)
+    }
+
+    fn generate_reported_pixels(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+        control: &GenerationControl,
+    ) -> Vec<PixelIntensity> {
+        if let Some(certified) = Self::certified(fragment_task, descriptor) {
+            let certainties = certified.classify_pixels(&PixelGrid::new(fragment_task), indices, fragment_task.max_iteration);
+            return Certainty::encode_reported(&certainties, 0, fragment_task.max_iteration, control);
+        }
+        let records = self.generate_record_pixels(fragment_task, descriptor, indices);
+        control.report_records(0, &records);
+        self.project_records(fragment_task, descriptor, &records)
+    }
+
+    fn project_records(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        records: &[PixelRecord],
+    ) -> Vec<PixelIntensity> {
+        let FractalDescriptor::Julia(julia_descriptor) = descriptor else {
+            return Vec::new();
+        };
+        let threshold = julia_descriptor.divergence_threshold_square;
+        let grid = PixelGrid::new(fragment_task);
+        records
+            .iter()
+            .map(|record| Self::encode(fragment_task, &grid, threshold, record))
+            .collect()
+    }
 
-        pixel_intensity_vec
+    fn generate_record_pixels(
+        &self,
+        fragment_task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelRecord> {
+        let Some(escape_time) = Self::escape_time(fragment_task, descriptor) else {
+            return Vec::new();
+        };
+        escape_time
+            .escape_pixels(&PixelGrid::new(fragment_task), indices, fragment_task.max_iteration)
+            .iter()
+            .map(|orbit| orbit.record(fragment_task.max_iteration))
+            .collect()
     }
 }
@@ -1,11 +1,49 @@
+mod adaptive_iterations;
+mod anti_aliasing;
+mod batch;
+mod certified;
+mod control;
+mod escape_time;
+mod external_rays;
 mod fraktal;
+mod grid;
 mod iterated_sin_z_fraktal;
 mod jullia_fractal;
+mod mandelbrot_deep_zoom_fractal;
 mod mandelbrot_fractal;
+mod multibrot_fractal;
+mod orbit_average;
+mod newton;
+mod newton_raphson_transcendental_fraktal;
 mod newton_raphson_z3_fraktal;
+mod parallel;
+mod perturbation;
+mod precision;
+mod progressive;
+mod subdivision;
 
+pub use adaptive_iterations::{generate_adaptive, initial_iteration_limit};
+pub use anti_aliasing::supersample;
+pub use batch::{OrbitBatch, LANES};
+pub use certified::{Certainty, CertifiedQuadratic};
+pub use control::{CancellationToken, Cancelled, GenerationControl, ProgressCounter, ProgressSink};
+pub use escape_time::*;
+pub use external_rays::*;
 pub use fraktal::Fractal;
+pub use grid::PixelGrid;
 pub use iterated_sin_z_fraktal::*;
 pub use jullia_fractal::*;
+pub use mandelbrot_deep_zoom_fractal::*;
 pub use mandelbrot_fractal::*;
-pub use newton_raphson_z3_fraktal::*;
\ No newline at end of file
+pub use multibrot_fractal::*;
+pub use newton::{smooth_convergence_count, NON_CONVERGED_ROOT};
+pub use newton_raphson_transcendental_fraktal::*;
+pub use newton_raphson_z3_fraktal::*;
+pub use parallel::{
+    generate_parallel, generate_pixels_parallel, generate_record_pixels_parallel, generate_records_parallel,
+    stream_parallel,
+};
+pub use perturbation::*;
+pub use precision::{relative_pixel_spacing, select_precision};
+pub use progressive::{generate_progressive, ProgressivePass};
+pub use subdivision::generate_subdivided;
@@ -0,0 +1,220 @@
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use common::{BigComplex, Complex, ComplexTrait, FractalDescriptor, PixelIntensity, PixelRecord};
+use networking::FragmentTask;
+use crate::escape_time::{iterate_to_large_bailout, QuadraticFormula, smooth_iteration_count};
+use crate::fraktal::Fractal;
+use crate::grid::PixelGrid;
+use crate::perturbation::{perturbed_escape, ReferenceOrbit, SeriesApproximation};
+
+/// Reference orbit kept between the row bands of a task, with the center, precision and
+/// iteration limit it was computed for.
+type CachedReference = (String, String, u32, u32, Arc<ReferenceOrbit>);
+
+/// Mandelbrot set rendered by perturbation around a high-precision center.
+///
+/// The range of the task holds offsets from the center of the descriptor, so views far
+/// smaller than the `f64` spacing around the center can be described and rendered.
+pub struct FractalMandelbrotDeepZoom {
+    reference: Mutex<Option<CachedReference>>,
+}
+
+impl FractalMandelbrotDeepZoom {
+    pub fn new() -> FractalMandelbrotDeepZoom {
+        FractalMandelbrotDeepZoom {
+            reference: Mutex::new(None),
+        }
+    }
+
+    /// Returns the reference orbit for the given center, computing it only once when
+    /// several row bands of the same task are generated.
+    fn reference(
+        &self,
+        center_re: &str,
+        center_im: &str,
+        precision: u32,
+        max_iteration: u32,
+    ) -> Result<Arc<ReferenceOrbit>, String> {
+        let mut cache = self.reference.lock().expect("reference orbit cache poisoned");
+        if let Some((re, im, bits, iterations, reference)) = cache.as_ref() {
+            if re == center_re && im == center_im && *bits == precision && *iterations == max_iteration {
+                return Ok(Arc::clone(reference));
+            }
+        }
+
+        let reference = Arc::new(ReferenceOrbit::compute(center_re, center_im, precision, max_iteration)?);
+        *cache = Some((
+            center_re.to_string(),
+            center_im.to_string(),
+            precision,
+            max_iteration,
+            Arc::clone(&reference),
+        ));
+        Ok(reference)
+    }
+
+    /// Checks that the center of a deep-zoom task parses at the precision its reference
+    /// orbit is computed with, so that its pixels can be generated.
+    ///
+    /// ```
+    /// use common::{FractalDescriptor, MandelbrotDeepZoomDescriptor, Point, Range, Resolution, U8Data};
+    /// use fraktals::FractalMandelbrotDeepZoom;
+    /// use networking::FragmentTask;
+    ///
+    /// let task = |center_re: &str| {
+    ///     let descriptor = FractalDescriptor::MandelbrotDeepZoom(MandelbrotDeepZoomDescriptor {
+    ///         center_re: center_re.to_string(),
+    ///         center_im: "0.1".to_string(),
+    ///     });
+    ///     let range = Range { min: Point { x: -1e-20, y: -1e-20 }, max: Point { x: 1e-20, y: 1e-20 } };
+    ///     FragmentTask::new(U8Data { offset: 0, count: 0 }, 100, Resolution { nx: 8, ny: 8 }, range, descriptor)
+    /// };
+    ///
+    /// assert!(FractalMandelbrotDeepZoom::validate(&task("-0.75")).is_ok());
+    /// assert!(FractalMandelbrotDeepZoom::validate(&task("-0.7.5")).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if `task` is not a deep zoom or a coordinate of its center is not a
+    /// valid decimal number.
+    pub fn validate(task: &FragmentTask) -> Result<(), String> {
+        let FractalDescriptor::MandelbrotDeepZoom(descriptor) = &task.fractal else {
+            return Err("not a deep-zoom Mandelbrot task".to_string());
+        };
+        BigComplex::parse(&descriptor.center_re, &descriptor.center_im, Self::reference_precision(task))
+            .map(|_| ())
+            .map_err(|e| format!("invalid deep-zoom center: {e}"))
+    }
+
+    /// Mantissa bits of the reference orbit of `task`.
+    fn reference_precision(task: &FragmentTask) -> u32 {
+        let grid = PixelGrid::new(task);
+        ReferenceOrbit::precision_for(grid.x_step.min(grid.y_step))
+    }
+}
+
+impl Default for FractalMandelbrotDeepZoom {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FractalMandelbrotDeepZoom {
+    /// Computes the records of the pixels of the fragment at the given offsets from the
+    /// center, in order.
+    ///
+    /// Tasks are checked by [`FractalMandelbrotDeepZoom::validate`] when they are read, so
+    /// no record is computed, and the error is logged, only for a task that skipped it.
+    fn generate_points(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        points: impl Iterator<Item = Complex>,
+    ) -> Vec<PixelRecord> {
+        let FractalDescriptor::MandelbrotDeepZoom(deep_zoom_descriptor) = descriptor else {
+            eprintln!("Task {}: deep-zoom Mandelbrot given another fractal", task.id.offset);
+            return Vec::new();
+        };
+        let max_iteration = task.max_iteration;
+        let grid = PixelGrid::new(task);
+        let pixel_spacing = grid.x_step.min(grid.y_step);
+        let reference = match self.reference(
+            &deep_zoom_descriptor.center_re,
+            &deep_zoom_descriptor.center_im,
+            Self::reference_precision(task),
+            max_iteration,
+        ) {
+            Ok(reference) => reference,
+            Err(e) => {
+                eprintln!("Task {}: invalid deep-zoom center: {}", task.id.offset, e);
+                return Vec::new();
+            }
+        };
+
+        let max_delta = [task.range.min.x, task.range.max.x]
+            .iter()
+            .flat_map(|x| [task.range.min.y, task.range.max.y].map(|y| x.hypot(y)))
+            .fold(0.0, f64::max);
+        let series = SeriesApproximation::new(&reference, max_delta, pixel_spacing);
+
+        points
+            .map(|delta_c| {
+                let (zn, count) = perturbed_escape(&reference, &series, &delta_c, max_iteration);
+                let smooth_count = (task.options.smooth_iterations && count < max_iteration).then(|| {
+                    // Past the divergence radius the orbit no longer needs perturbation:
+                    // the pixel rounded to f64 is as good as exact for a few iterations.
+                    let c = reference.points()[1].add(&delta_c);
+                    let formula = QuadraticFormula { divergence_threshold_square: 4.0 };
+                    let mut z = zn;
+                    let bailout_iterations = iterate_to_large_bailout(&formula, &mut z, &c, |_| {});
+                    smooth_iteration_count(count + bailout_iterations, z, 2.0)
+                });
+                PixelRecord {
+                    count,
+                    smooth_count,
+                    z: Some(zn),
+                    ..PixelRecord::default()
+                }
+            })
+            .collect()
+    }
+
+    /// Projects the record of a pixel onto the intensity sent to the server.
+    fn encode(task: &FragmentTask, record: &PixelRecord) -> PixelIntensity {
+        let intensity = record.normalized_count(task.max_iteration);
+        let escape_time = record.z.map_or(0.0, |z| z.square_norm()) as f32 / 4.0;
+
+        PixelIntensity::new(escape_time, intensity)
+    }
+}
+
+impl Fractal for FractalMandelbrotDeepZoom {
+    fn generate_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_rows(task, descriptor, rows);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn generate_record_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelRecord> {
+        self.generate_points(task, descriptor, PixelGrid::new(task).row_points(rows))
+    }
+
+    fn generate_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_pixels(task, descriptor, indices);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn project_records(
+        &self,
+        task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        records: &[PixelRecord],
+    ) -> Vec<PixelIntensity> {
+        records.iter().map(|record| Self::encode(task, record)).collect()
+    }
+
+    fn generate_record_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelRecord> {
+        let grid = PixelGrid::new(task);
+        self.generate_points(task, descriptor, indices.iter().map(|index| grid.index_point(*index)))
+    }
+}
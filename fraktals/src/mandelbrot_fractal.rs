@@ -1,6 +1,12 @@
-use common::{Complex, ComplexTrait, FractalDescriptor, PixelIntensity};
+use std::ops::Range;
+
+use common::{ComplexTrait, FractalDescriptor, PixelIntensity, PixelRecord, Plane};
 use networking::FragmentTask;
+use crate::certified::{Certainty, CertifiedQuadratic};
+use crate::control::GenerationControl;
+use crate::escape_time::{EscapeTime, QuadraticFormula};
 use crate::fraktal::Fractal;
+use crate::grid::PixelGrid;
 
 pub struct FractalMandelbrot {}
 
@@ -8,46 +14,160 @@ impl FractalMandelbrot {
     pub fn new() -> FractalMandelbrot {
         FractalMandelbrot {}
     }
+
+    /// Plane drawn for `descriptor`, the parameter plane by default.
+    fn plane(descriptor: &FractalDescriptor) -> Plane {
+        let plane = match descriptor {
+            FractalDescriptor::Mandelbrot(mandelbrot_descriptor) => mandelbrot_descriptor.plane,
+            _ => None,
+        };
+        plane.unwrap_or(Plane::Parameter { z0: None })
+    }
+
+    /// Escape-time driver configured for `task`.
+    fn escape_time(task: &FragmentTask, descriptor: &FractalDescriptor) -> EscapeTime<QuadraticFormula> {
+        EscapeTime::new(
+            QuadraticFormula { divergence_threshold_square: 4.0 },
+            Self::plane(descriptor),
+        )
+        .with_interior_detection(task.options.interior_detection)
+        .with_subdivision(task.options.subdivision)
+        .with_precision(task.options.precision.unwrap_or_default())
+        .with_distance_estimation(task.options.distance_estimation)
+        .with_smooth_iterations(task.options.smooth_iterations)
+        .with_orbit_trap(task.options.orbit_trap.clone())
+        .with_orbit_average(task.options.orbit_average)
+    }
+
+    /// Projects the record of a pixel of `grid` onto the intensity sent to the server.
+    fn encode(task: &FragmentTask, grid: &PixelGrid, record: &PixelRecord) -> PixelIntensity {
+        let intensity = record.normalized_count(task.max_iteration);
+        let escape_time = if task.options.distance_estimation {
+            let distance = record.distance.unwrap_or(0.0);
+            (distance / grid.x_step.min(grid.y_step)) as f32
+        } else if task.options.orbit_trap.is_some() {
+            record.trap_distance.unwrap_or(f64::INFINITY) as f32
+        } else if task.options.orbit_average.is_some() {
+            record.average.unwrap_or(0.0) as f32
+        } else if task.options.record_period {
+            record.period.unwrap_or(0) as f32
+        } else {
+            record.z.map_or(0.0, |z| z.square_norm()) as f32 / 4.0
+        };
+
+        PixelIntensity::new(escape_time, intensity)
+    }
+}
+
+impl Default for FractalMandelbrot {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Fractal for FractalMandelbrot {
-    fn generate(&self, task: &FragmentTask, _descriptor: &FractalDescriptor) -> Vec<PixelIntensity> {
-        let x_start = task.range.min.x;
-        let x_end = task.range.max.x;
-        let y_start = task.range.min.y;
-        let y_end = task.range.max.y;
-
-        let x_step = ((&x_start - &x_end) / task.resolution.nx as f64).abs();
-        let y_step = ((&y_start - &y_end) / task.resolution.ny as f64).abs();
-
-        let mut pixel_intensity_vec: Vec<PixelIntensity> = Vec::new();
-        let max_iteration = task.max_iteration;
-
-        let mut x = x_start;
-        let mut y = y_start;
-
-        while y < y_end {
-            while x < x_end {
-                let c = Complex::new(x, y);
-                let mut zn = Complex::new(0.0, 0.0);
-                let mut count = 0;
-
-                while zn.square_norm() < 4.0 && count < max_iteration {
-                    zn = zn.multiply(&zn).add(&c);
-                    count += 1;
-                }
-
-                let intensity = count as f32 / max_iteration as f32;
-                let escape_time = zn.square_norm() as f32 / 4.0;
-
-                pixel_intensity_vec.push(PixelIntensity::new(escape_time, intensity));
-                x += x_step;
-            }
-            x = x_start;
-            y += y_step;
+    fn generate_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelIntensity> {
+        if task.options.certified {
+            return CertifiedQuadratic::new(Self::plane(descriptor))
+                .classify_rows(&PixelGrid::new(task), rows, task.max_iteration)
+                .into_iter()
+                .map(|certainty| certainty.encode(task.max_iteration))
+                .collect();
+        }
+        let records = self.generate_record_rows(task, descriptor, rows);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn generate_record_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelRecord> {
+        Self::escape_time(task, descriptor)
+            .escape_rows(&PixelGrid::new(task), rows, task.max_iteration)
+            .iter()
+            .map(|orbit| orbit.record(task.max_iteration))
+            .collect()
+    }
+
+    fn generate_reported_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+        control: &GenerationControl,
+    ) -> Vec<PixelIntensity> {
+        let row_count = rows.len();
+        if task.options.certified {
+            let certainties = CertifiedQuadratic::new(Self::plane(descriptor))
+                .classify_rows(&PixelGrid::new(task), rows, task.max_iteration);
+            return Certainty::encode_reported(&certainties, row_count, task.max_iteration, control);
+        }
+        let records = self.generate_record_rows(task, descriptor, rows);
+        control.report_records(row_count, &records);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn generate_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelIntensity> {
+        if task.options.certified {
+            return CertifiedQuadratic::new(Self::plane(descriptor))
+                .classify_pixels(&PixelGrid::new(task), indices, task.max_iteration)
+                .into_iter()
+                .map(|certainty| certainty.encode(task.max_iteration))
+                .collect();
+        }
+        let records = self.generate_record_pixels(task, descriptor, indices);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn generate_reported_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+        control: &GenerationControl,
+    ) -> Vec<PixelIntensity> {
+        if task.options.certified {
+            let certainties = CertifiedQuadratic::new(Self::plane(descriptor))
+                .classify_pixels(&PixelGrid::new(task), indices, task.max_iteration);
+            return Certainty::encode_reported(&certainties, 0, task.max_iteration, control);
         }
+        let records = self.generate_record_pixels(task, descriptor, indices);
+        control.report_records(0, &records);
+        self.project_records(task, descriptor, &records)
+    }
 
-        pixel_intensity_vec
+    fn project_records(
+        &self,
+        task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        records: &[PixelRecord],
+    ) -> Vec<PixelIntensity> {
+        let grid = PixelGrid::new(task);
+        records.iter().map(|record| Self::encode(task, &grid, record)).collect()
     }
 
+    fn generate_record_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelRecord> {
+        Self::escape_time(task, descriptor)
+            .escape_pixels(&PixelGrid::new(task), indices, task.max_iteration)
+            .iter()
+            .map(|orbit| orbit.record(task.max_iteration))
+            .collect()
+    }
 }
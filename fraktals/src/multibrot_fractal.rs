@@ -0,0 +1,118 @@
+use std::ops::Range;
+
+use common::{ComplexTrait, FractalDescriptor, PixelIntensity, PixelRecord, Plane};
+use networking::FragmentTask;
+use crate::escape_time::{EscapeTime, MultibrotFormula};
+use crate::fraktal::Fractal;
+use crate::grid::PixelGrid;
+
+pub struct FractalMultibrot {}
+
+impl FractalMultibrot {
+    pub fn new() -> FractalMultibrot {
+        FractalMultibrot {}
+    }
+
+    /// Escape-time driver configured for `task`, `None` for another fractal.
+    fn escape_time(task: &FragmentTask, descriptor: &FractalDescriptor) -> Option<EscapeTime<MultibrotFormula>> {
+        let FractalDescriptor::Multibrot(multibrot_descriptor) = descriptor else {
+            return None;
+        };
+        let escape_time = EscapeTime::new(
+            MultibrotFormula { exponent: multibrot_descriptor.exponent.max(2) },
+            multibrot_descriptor.plane.unwrap_or(Plane::Parameter { z0: None }),
+        )
+        .with_interior_detection(task.options.interior_detection)
+        .with_subdivision(task.options.subdivision)
+        .with_precision(task.options.precision.unwrap_or_default())
+        .with_smooth_iterations(task.options.smooth_iterations)
+        .with_orbit_trap(task.options.orbit_trap.clone())
+        .with_orbit_average(task.options.orbit_average);
+        Some(escape_time)
+    }
+
+    /// Projects the record of a pixel onto the intensity sent to the server.
+    fn encode(task: &FragmentTask, record: &PixelRecord) -> PixelIntensity {
+        let intensity = record.normalized_count(task.max_iteration);
+        let escape_time = if task.options.orbit_trap.is_some() {
+            record.trap_distance.unwrap_or(f64::INFINITY) as f32
+        } else if task.options.orbit_average.is_some() {
+            record.average.unwrap_or(0.0) as f32
+        } else if task.options.record_period {
+            record.period.unwrap_or(0) as f32
+        } else {
+            record.z.map_or(0.0, |z| z.square_norm()) as f32 / 4.0
+        };
+
+        PixelIntensity::new(escape_time, intensity)
+    }
+}
+
+impl Default for FractalMultibrot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fractal for FractalMultibrot {
+    fn generate_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_rows(task, descriptor, rows);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn generate_record_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelRecord> {
+        let Some(escape_time) = Self::escape_time(task, descriptor) else {
+            return Vec::new();
+        };
+        escape_time
+            .escape_rows(&PixelGrid::new(task), rows, task.max_iteration)
+            .iter()
+            .map(|orbit| orbit.record(task.max_iteration))
+            .collect()
+    }
+
+    fn generate_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_pixels(task, descriptor, indices);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn project_records(
+        &self,
+        task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        records: &[PixelRecord],
+    ) -> Vec<PixelIntensity> {
+        records.iter().map(|record| Self::encode(task, record)).collect()
+    }
+
+    fn generate_record_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelRecord> {
+        let Some(escape_time) = Self::escape_time(task, descriptor) else {
+            return Vec::new();
+        };
+        escape_time
+            .escape_pixels(&PixelGrid::new(task), indices, task.max_iteration)
+            .iter()
+            .map(|orbit| orbit.record(task.max_iteration))
+            .collect()
+    }
+}
@@ -0,0 +1,17 @@
+//! Helpers shared by the Newton-Raphson fractals.
+
+/// Value written in `zn` for points whose Newton iteration did not converge.
+pub const NON_CONVERGED_ROOT: f32 = -1.0;
+
+/// Returns the continuous number of Newton steps `n + f` of a point whose step `dz_n`
+/// fell below `tolerance` (squared modulus) after `n` larger steps, the last of which was
+/// `dz_{n-1}`: `f` interpolates `ln|dz|²` linearly between the two steps to find where it
+/// crossed `ln(tolerance)`. `f` is `0` for points converging at their first step.
+pub fn smooth_convergence_count(count: u32, previous_step_square: Option<f64>, step_square: f64, tolerance: f64) -> f64 {
+    let Some(previous_step_square) = previous_step_square else {
+        return count as f64;
+    };
+    let (previous, last) = (previous_step_square.ln(), step_square.ln());
+    let fraction = (tolerance.ln() - previous) / (last - previous);
+    count as f64 + if fraction.is_finite() { fraction.clamp(0.0, 1.0) } else { 0.0 }
+}
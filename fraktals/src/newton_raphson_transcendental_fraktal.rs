@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use common::{Complex, ComplexTrait, FractalDescriptor, PixelIntensity, PixelRecord, TranscendentalFunction};
+use networking::FragmentTask;
+use crate::fraktal::Fractal;
+use crate::grid::PixelGrid;
+use crate::newton::{smooth_convergence_count, NON_CONVERGED_ROOT};
+
+/// Most extra Newton steps refining a converged point before identifying its root, so
+/// that every orbit reaching the same root ends up on the same canonical coordinates.
+/// Refinement stops earlier once the steps no longer shrink.
+const MAX_POLISH_STEPS: u32 = 16;
+
+/// Ratio between the grid root IDs are rounded to and the error left on a refined root,
+/// a few units in the last place of its coordinates, so that two refinements of the same
+/// root almost never round to different cells.
+const ID_GRID_MARGIN: f64 = 1e6;
+
+/// Newton-Raphson fractal for functions with an unbounded number of roots.
+///
+/// Roots are discovered on the fly and identified by a stable ID derived from their
+/// canonical coordinates (see [`RootCatalog`]), so adjacent tiles computed by different workers agree on
+/// which root each basin belongs to:
+///
+/// ```
+/// use common::{FractalDescriptor, NewtonRaphsonTranscendentalDescriptor, Point, Range, Resolution};
+/// use common::{TranscendentalFunction, U8Data};
+/// use fraktals::{Fractal, NewtonRaphsonTranscendentalFractal};
+/// use networking::FragmentTask;
+///
+/// let descriptor = FractalDescriptor::NewtonRaphsonTranscendental(NewtonRaphsonTranscendentalDescriptor {
+///     function: TranscendentalFunction::ZSinZMinusOne,
+///     root_tolerance: 1e-6,
+/// });
+/// let tile = |min_x: f64| {
+///     let range = Range { min: Point { x: min_x, y: -2.0 }, max: Point { x: min_x + 20.0, y: 2.0 } };
+///     let task = FragmentTask::new(U8Data { offset: 0, count: 0 }, 100, Resolution { nx: 80, ny: 16 }, range, descriptor.clone());
+///     NewtonRaphsonTranscendentalFractal::new().generate_records(&task, &descriptor)
+/// };
+///
+/// let (left, right) = (tile(-10.0), tile(3.3));
+/// let mut shared = 0;
+/// for a in left.iter().filter(|record| record.root.is_some()) {
+///     for b in right.iter().filter(|record| record.root.is_some()) {
+///         let (za, zb) = (a.z.unwrap(), b.z.unwrap());
+///         if (za.re - zb.re).hypot(za.im - zb.im) < 1e-6 {
+///             assert_eq!(a.root, b.root, "root {za:?} identified differently");
+///             shared += 1;
+///         }
+///     }
+/// }
+/// assert!(shared > 0);
+/// ```
+pub struct NewtonRaphsonTranscendentalFractal {}
+
+impl NewtonRaphsonTranscendentalFractal {
+    pub fn new() -> Self {
+        NewtonRaphsonTranscendentalFractal {}
+    }
+
+    fn evaluate(function: TranscendentalFunction, z: &Complex) -> (Complex, Complex) {
+        match function {
+            TranscendentalFunction::Sin => (z.sine(), z.cosine()),
+            TranscendentalFunction::ExpMinusOne => {
+                let exp = z.exponential();
+                (exp.subtract(&Complex::new(1.0, 0.0)), exp)
+            }
+            TranscendentalFunction::ZSinZMinusOne => {
+                let sin = z.sine();
+                let fz = z.multiply(&sin).subtract(&Complex::new(1.0, 0.0));
+                let dfz = sin.add(&z.multiply(&z.cosine()));
+                (fz, dfz)
+            }
+        }
+    }
+
+    fn newton_step(function: TranscendentalFunction, z: &Complex) -> Complex {
+        let (fz, dfz) = Self::evaluate(function, z);
+        fz.divide(dfz)
+    }
+
+    /// Refines the converged point `z` by Newton steps until they stop shrinking, which
+    /// leaves it within rounding error of its root.
+    fn polish(function: TranscendentalFunction, mut z: Complex) -> Complex {
+        let mut previous_step = f64::INFINITY;
+        for _ in 0..MAX_POLISH_STEPS {
+            let dz = Self::newton_step(function, &z);
+            let step = dz.square_norm();
+            if step.is_nan() || step >= previous_step {
+                break;
+            }
+            z = z.subtract(&dz);
+            previous_step = step;
+        }
+        z
+    }
+
+    /// Computes the records of the given pixels of the fragment, in order.
+    fn generate_points(
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        points: impl Iterator<Item = Complex>,
+    ) -> Vec<PixelRecord> {
+        let FractalDescriptor::NewtonRaphsonTranscendental(newton_descriptor) = descriptor else {
+            return Vec::new();
+        };
+        let function = newton_descriptor.function;
+
+        let mut records: Vec<PixelRecord> = Vec::new();
+        let mut catalog = RootCatalog::new(newton_descriptor.root_tolerance);
+
+        let max_iteration = task.max_iteration;
+        let tolerance = 1e-6;
+
+        for pixel in points {
+            let mut z = pixel;
+            let mut count = 0;
+            let mut converged = false;
+            let mut previous_step = None;
+            let mut smooth_count = None;
+
+            while count < max_iteration {
+                let dz = Self::newton_step(function, &z);
+                z = z.subtract(&dz);
+
+                let step = dz.square_norm();
+                if !step.is_finite() {
+                    break;
+                }
+                if step < tolerance {
+                    converged = true;
+                    smooth_count = task
+                        .options
+                        .smooth_iterations
+                        .then(|| smooth_convergence_count(count, previous_step, step, tolerance));
+                    break;
+                }
+
+                previous_step = Some(step);
+                count += 1;
+            }
+
+            if converged {
+                z = Self::polish(function, z);
+                converged = z.square_norm().is_finite();
+            }
+
+            records.push(PixelRecord {
+                count,
+                smooth_count: smooth_count.filter(|_| converged),
+                z: Some(z),
+                root: converged.then(|| catalog.identify(&z)),
+                ..PixelRecord::default()
+            });
+        }
+
+        if catalog.collisions() > 0 {
+            eprintln!("Task {}: {} root ID collisions", task.id.offset, catalog.collisions());
+        }
+
+        records
+    }
+
+    /// Projects the record of a pixel onto the intensity sent to the server.
+    fn encode(task: &FragmentTask, record: &PixelRecord) -> PixelIntensity {
+        let root = record.root.map_or(NON_CONVERGED_ROOT, |root| root as f32);
+        let normalized_count = record.normalized_count(task.max_iteration);
+
+        PixelIntensity::new(root, normalized_count)
+    }
+}
+
+impl Default for NewtonRaphsonTranscendentalFractal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Roots discovered while rendering a fragment, each identified by the cell of the
+/// tolerance grid holding its refined coordinates.
+///
+/// The ID of a root is a 24-bit hash of its cell, small enough to be stored exactly in
+/// the `f32` of a [`PixelIntensity`], which does not depend on the order in which roots
+/// were found. Distinct cells hashing to the same ID are detected: the later one takes the
+/// next free ID of its probe sequence, which then depends on the fragment, and the
+/// collision is counted.
+///
+/// ```
+/// use common::{Complex, ComplexTrait};
+/// use fraktals::RootCatalog;
+///
+/// let mut catalog = RootCatalog::new(1.0);
+/// let first = catalog.identify(&Complex::new(948.0, 0.0));
+/// assert_eq!(catalog.identify(&Complex::new(948.2, 0.0)), first);
+/// assert_eq!(catalog.collisions(), 0);
+///
+/// // The cells of 948 and 2263 hash to the same ID.
+/// let second = catalog.identify(&Complex::new(2263.0, 0.0));
+/// assert_ne!(second, first);
+/// assert_eq!(catalog.identify(&Complex::new(2263.0, 0.0)), second);
+/// assert_eq!(catalog.collisions(), 1);
+/// assert_eq!(catalog.roots().len(), 2);
+/// ```
+pub struct RootCatalog {
+    tolerance: f64,
+    cells: HashMap<u32, (i64, i64)>,
+    roots: Vec<(Complex, u32)>,
+    collisions: usize,
+}
+
+impl RootCatalog {
+    /// Creates an empty catalog identifying roots by cells of `tolerance` wide.
+    pub fn new(tolerance: f64) -> Self {
+        RootCatalog {
+            tolerance,
+            cells: HashMap::new(),
+            roots: Vec::new(),
+            collisions: 0,
+        }
+    }
+
+    /// Returns the ID of the cell containing `root`, registering it if needed. `root` must
+    /// be refined to rounding error, for its ID to match the one other fragments give it.
+    pub fn identify(&mut self, root: &Complex) -> u32 {
+        let cell = Self::cell(root, self.tolerance);
+        for probe in 0.. {
+            let id = Self::canonical_id(cell, probe);
+            match self.cells.get(&id) {
+                Some(known) if *known == cell => return id,
+                Some(_) => {}
+                None => {
+                    self.collisions += probe as usize;
+                    self.cells.insert(id, cell);
+                    self.roots.push((*root, id));
+                    return id;
+                }
+            }
+        }
+        unreachable!("every probe of the root ID collided")
+    }
+
+    /// Roots discovered so far, with their IDs.
+    pub fn roots(&self) -> &[(Complex, u32)] {
+        &self.roots
+    }
+
+    /// Number of IDs new cells found already taken by other ones.
+    pub fn collisions(&self) -> usize {
+        self.collisions
+    }
+
+    /// Rounds the root coordinates to the tolerance grid, coarsened to [`ID_GRID_MARGIN`]
+    /// times the rounding error of the coordinates.
+    fn cell(root: &Complex, tolerance: f64) -> (i64, i64) {
+        let error = f64::EPSILON * root.re.abs().max(root.im.abs()).max(1.0);
+        let grid = tolerance.max(ID_GRID_MARGIN * error);
+        ((root.re / grid).round() as i64, (root.im / grid).round() as i64)
+    }
+
+    /// Hashes `cell` into the 24-bit ID tried at step `probe` of its probe sequence.
+    fn canonical_id((re, im): (i64, i64), probe: u64) -> u32 {
+        let mut hash = (re as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (im as u64).rotate_left(32);
+        hash = hash.wrapping_add(probe.wrapping_mul(0xC2B2_AE3D_27D4_EB4F));
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+        hash ^= hash >> 33;
+
+        (hash & 0x00FF_FFFF) as u32
+    }
+}
+
+impl Fractal for NewtonRaphsonTranscendentalFractal {
+    fn generate_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_rows(task, descriptor, rows);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn generate_record_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelRecord> {
+        Self::generate_points(task, descriptor, PixelGrid::new(task).row_points(rows))
+    }
+
+    fn generate_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_pixels(task, descriptor, indices);
+        self.project_records(task, descriptor, &records)
+    }
+
+    fn project_records(
+        &self,
+        task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        records: &[PixelRecord],
+    ) -> Vec<PixelIntensity> {
+        records.iter().map(|record| Self::encode(task, record)).collect()
+    }
+
+    fn generate_record_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelRecord> {
+        let grid = PixelGrid::new(task);
+        Self::generate_points(task, descriptor, indices.iter().map(|index| grid.index_point(*index)))
+    }
+}
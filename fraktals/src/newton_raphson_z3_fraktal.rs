@@ -1,7 +1,32 @@
-use common::{Complex, ComplexTrait, FractalDescriptor, PixelIntensity};
+use std::ops::Range;
+
+use common::{Complex, ComplexTrait, FractalDescriptor, PixelIntensity, PixelRecord};
 use networking::FragmentTask;
 use crate::fraktal::Fractal;
-
+use crate::grid::PixelGrid;
+use crate::newton::{smooth_convergence_count, NON_CONVERGED_ROOT};
+
+/// Newton-Raphson fractal of `z^3 - 1`.
+///
+/// Points reaching `max_iteration` without converging get no root:
+///
+/// ```
+/// use common::{FractalDescriptor, NewtonRaphsonZ3Descriptor, Point, Range, Resolution, U8Data};
+/// use fraktals::{Fractal, NewtonRaphsonZ3Fractal, NON_CONVERGED_ROOT};
+/// use networking::FragmentTask;
+///
+/// let descriptor = FractalDescriptor::NewtonRaphsonZ3(NewtonRaphsonZ3Descriptor {});
+/// // The origin, where the derivative vanishes, and a point converging in a few steps.
+/// let range = Range { min: Point { x: 0.0, y: 0.0 }, max: Point { x: 2.0, y: 1.0 } };
+/// let task = FragmentTask::new(U8Data { offset: 0, count: 0 }, 1000, Resolution { nx: 2, ny: 1 }, range, descriptor.clone());
+/// let fractal = NewtonRaphsonZ3Fractal::new();
+///
+/// let records = fractal.generate_records(&task, &descriptor);
+/// assert_eq!((records[0].count, records[0].root), (task.max_iteration, None));
+/// assert_eq!(records[1].root, Some(0));
+/// let pixels = fractal.generate(&task, &descriptor);
+/// assert_eq!((pixels[0].zn, pixels[1].zn), (NON_CONVERGED_ROOT, 0.0));
+/// ```
 pub struct NewtonRaphsonZ3Fractal {}
 
 impl NewtonRaphsonZ3Fractal {
@@ -32,57 +57,115 @@ impl NewtonRaphsonZ3Fractal {
 
         min_index
     }
-}
-
-impl Fractal for NewtonRaphsonZ3Fractal {
-    fn generate(&self, task: &FragmentTask, _descriptor: &FractalDescriptor) -> Vec<PixelIntensity> {
-        let x_start = task.range.min.x;
-        let x_end = task.range.max.x;
-        let y_start = task.range.min.y;
-        let y_end = task.range.max.y;
-
-        let x_step = ((x_start - x_end) / task.resolution.nx as f64).abs();
-        let y_step = ((y_start - y_end) / task.resolution.ny as f64).abs();
 
-        let mut pixel_intensity_vec: Vec<PixelIntensity> = Vec::new();
+    /// Computes the records of the given pixels of the fragment, in order.
+    fn generate_points(task: &FragmentTask, points: impl Iterator<Item = Complex>) -> Vec<PixelRecord> {
+        let mut records: Vec<PixelRecord> = Vec::new();
 
         let max_iteration = task.max_iteration;
         let tolerance = 1e-6;
 
-        let mut x = x_start;
-        let mut y = y_start;
+        for pixel in points {
+            let mut z = pixel;
+            let mut count = 0;
+            let mut converged = false;
+            let mut previous_step = None;
+            let mut smooth_count = None;
+
+            while count < max_iteration {
+                let fz = z.multiply(&z).multiply(&z).subtract(&Complex::new(1.0, 0.0));
+                let dfz = z.multiply(&z).multiply(&Complex::new(3.0, 0.0));
+                let dz = fz.divide(dfz);
+
+                z = z.subtract(&dz);
+
+                let step = dz.square_norm();
+                if step < tolerance {
+                    converged = true;
+                    smooth_count = task
+                        .options
+                        .smooth_iterations
+                        .then(|| smooth_convergence_count(count, previous_step, step, tolerance));
+                    break;
+                }
 
-        while y < y_end {
-            while x < x_end {
-                let mut z = Complex::new(x, y);
-                let mut count = 0;
+                previous_step = Some(step);
+                count += 1;
+            }
 
-                while count < max_iteration {
-                    let fz = z.multiply(&z).multiply(&z).subtract(&Complex::new(1.0, 0.0));
-                    let dfz = z.multiply(&z).multiply(&Complex::new(3.0, 0.0));
-                    let dz = fz.divide(dfz);
+            records.push(PixelRecord {
+                count,
+                smooth_count,
+                z: Some(z),
+                root: converged.then(|| Self::closest_root_index(&z) as u32),
+                ..PixelRecord::default()
+            });
+        }
 
-                    z = z.subtract(&dz);
+        records
+    }
 
-                    if dz.square_norm() < tolerance {
-                        break;
-                    }
+    /// Projects the record of a pixel onto the intensity sent to the server.
+    fn encode(task: &FragmentTask, record: &PixelRecord) -> PixelIntensity {
+        let root_index = record.root.map_or(NON_CONVERGED_ROOT, |root| root as f32);
+        let normalized_count = record.normalized_count(task.max_iteration);
 
-                    count += 1;
-                }
+        PixelIntensity::new(root_index, normalized_count)
+    }
+}
 
-                let root_index = Self::closest_root_index(&z) as f32;
-                let normalized_count = count as f32 / max_iteration as f32;
+impl Default for NewtonRaphsonZ3Fractal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-                pixel_intensity_vec.push(PixelIntensity::new(root_index, normalized_count));
+impl Fractal for NewtonRaphsonZ3Fractal {
+    fn generate_rows(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_rows(task, descriptor, rows);
+        self.project_records(task, descriptor, &records)
+    }
 
-                x += x_step;
-            }
-            x = x_start;
-            y += y_step;
-        }
+    fn generate_record_rows(
+        &self,
+        task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        rows: Range<usize>,
+    ) -> Vec<PixelRecord> {
+        Self::generate_points(task, PixelGrid::new(task).row_points(rows))
+    }
 
-        pixel_intensity_vec
+    fn generate_pixels(
+        &self,
+        task: &FragmentTask,
+        descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelIntensity> {
+        let records = self.generate_record_pixels(task, descriptor, indices);
+        self.project_records(task, descriptor, &records)
     }
 
+    fn project_records(
+        &self,
+        task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        records: &[PixelRecord],
+    ) -> Vec<PixelIntensity> {
+        records.iter().map(|record| Self::encode(task, record)).collect()
+    }
+
+    fn generate_record_pixels(
+        &self,
+        task: &FragmentTask,
+        _descriptor: &FractalDescriptor,
+        indices: &[usize],
+    ) -> Vec<PixelRecord> {
+        let grid = PixelGrid::new(task);
+        Self::generate_points(task, indices.iter().map(|index| grid.index_point(*index)))
+    }
 }
@@ -0,0 +1,101 @@
+//! Averaging colourings of the polynomial formulas `z = z^d + c`.
+//!
+//! A term `t(z_n)` between `0` and `1` is computed for every value of the orbit and
+//! averaged over the orbit. For escaped orbits, the averages `A_n` and `A_{n-1}` with and
+//! without the last term are blended with the fraction of an iteration
+//! `f = ln(ln|z_n| / ln R) / ln(d)` spent beyond the bailout radius `R`:
+//!
+//! `A = f A_{n-1} + (1 - f) A_n`,
+//!
+//! which varies continuously from pixel to pixel where the plain average jumps with the
+//! iteration count.
+
+use std::f64::consts::PI;
+
+use common::{Complex, ComplexTrait, OrbitAverage};
+
+/// Running average of the terms of an orbit.
+pub(crate) struct OrbitAverager {
+    average: OrbitAverage,
+    c: Complex,
+    /// Last two values of the orbit, the latest first.
+    previous: [Option<Complex>; 2],
+    sum: f64,
+    terms: u32,
+    /// Last term added, removed to get the average without it.
+    last_term: Option<f64>,
+}
+
+impl OrbitAverager {
+    /// Starts averaging the orbit of `z0` under `z^d + c`.
+    pub(crate) fn new(average: OrbitAverage, z0: Complex, c: Complex) -> Self {
+        OrbitAverager {
+            average,
+            c,
+            previous: [Some(z0), None],
+            sum: 0.0,
+            terms: 0,
+            last_term: None,
+        }
+    }
+
+    /// Adds the next value `z` of the orbit.
+    pub(crate) fn push(&mut self, z: Complex) {
+        self.last_term = self.term(&z);
+        if let Some(term) = self.last_term {
+            self.sum += term;
+            self.terms += 1;
+        }
+        self.previous = [Some(z), self.previous[0]];
+    }
+
+    /// Term of the new value `z` of the orbit, `None` when it is not defined.
+    fn term(&self, z: &Complex) -> Option<f64> {
+        let term = match self.average {
+            OrbitAverage::Stripe { density } => 0.5 + 0.5 * (density * z.argument()).sin(),
+            OrbitAverage::TriangleInequality => {
+                // With w = z_{n-1}^d = z_n - c, |z_n| lies between ||w| - |c|| and |w| + |c|.
+                // Differences of moduli are computed from |a|² - |b|² = (|a| - |b|)(|a| + |b|),
+                // which stays accurate when |w| is far larger than |c|.
+                let w = z.subtract(&self.c);
+                let (w_square, c_square) = (w.square_norm(), self.c.square_norm());
+                let (z_norm, w_norm, c_norm) = (z.square_norm().sqrt(), w_square.sqrt(), c_square.sqrt());
+                let cross = 2.0 * (w.re * self.c.re + w.im * self.c.im);
+                if w_norm >= c_norm {
+                    // |z_n| - (|w| - |c|), over a width of 2|c|.
+                    ((cross + c_square) / (z_norm + w_norm) + c_norm) / (2.0 * c_norm)
+                } else {
+                    // |z_n| - (|c| - |w|), over a width of 2|w|.
+                    ((cross + w_square) / (z_norm + c_norm) + w_norm) / (2.0 * w_norm)
+                }
+            }
+            OrbitAverage::Curvature => {
+                let [Some(previous), Some(before)] = self.previous else {
+                    return None;
+                };
+                z.subtract(&previous).divide(previous.subtract(&before)).argument().abs() / PI
+            }
+        };
+        term.is_finite().then_some(term)
+    }
+
+    /// Returns the average of the orbit, `None` when no term was defined.
+    pub(crate) fn average(&self) -> Option<f64> {
+        (self.terms > 0).then(|| self.sum / self.terms as f64)
+    }
+
+    /// Returns the average of an orbit that escaped at `z`, beyond the squared radius
+    /// `bailout_square`, under a formula of degree `degree`: the averages with and
+    /// without the last term blended. `None` when no term was defined.
+    pub(crate) fn escaped_average(&self, z: &Complex, bailout_square: f64, degree: f64) -> Option<f64> {
+        let average = self.average()?;
+        let (Some(last_term), 2..) = (self.last_term, self.terms) else {
+            return Some(average);
+        };
+
+        let previous_average = (self.sum - last_term) / (self.terms - 1) as f64;
+        let fraction = (z.square_norm().ln() / bailout_square.ln()).ln() / degree.ln();
+        let fraction = if fraction.is_nan() { 0.0 } else { fraction.clamp(0.0, 1.0) };
+        Some(fraction * previous_average + (1.0 - fraction) * average)
+    }
+}
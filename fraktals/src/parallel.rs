@@ -0,0 +1,446 @@
+//! Multithreaded generation of a fragment.
+//!
+//! The fragment is cut into bands of rows that a pool of scoped threads picks up one
+//! after the other. Each band is computed by [`Fractal::generate_rows`] exactly as in the
+//! single-threaded path, so the assembled output is bit-identical to [`Fractal::generate`].
+//! A [`GenerationControl`] is checked before each band and receives every computed band.
+//!
+//! Streamed generation writes the bands to a [`PixelSink`] in order as soon as they are
+//! computed, while threads keep computing the following ones.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Condvar, Mutex};
+use std::thread;
+
+use common::{FractalDescriptor, PixelIntensity, PixelRecord, PixelSink};
+use networking::FragmentTask;
+use crate::control::{Cancelled, GenerationControl};
+use crate::fraktal::{controlled_band_height, Fractal, CONTROLLED_BAND_PIXELS};
+
+/// Number of row bands handed out per thread, so that threads finishing early on cheap
+/// rows can pick up work left over by threads stuck on expensive ones. Bands are cut
+/// down to [`CONTROLLED_BAND_PIXELS`] pixels when the generation can be stopped, so that
+/// it stops soon after being cancelled.
+const BANDS_PER_THREAD: usize = 4;
+
+/// Generates the fragment described by `task` using up to `threads` threads.
+///
+/// With a single thread, or a fragment too small to be split, this is equivalent to
+/// calling [`Fractal::generate`], or [`Fractal::generate_controlled`] when `control` is
+/// not passive.
+///
+/// The output is bit-identical to iterating every pixel one by one with
+/// [`crate::EscapeTime::escape`]:
+///
+/// ```
+/// use common::{ComplexTrait, FractalDescriptor, MandelbrotDescriptor, MultibrotDescriptor};
+/// use common::{Plane, Point, Range, Resolution, U8Data};
+/// use fraktals::{generate_parallel, EscapeTime, EscapeTimeFormula, FractalMandelbrot, FractalMultibrot};
+/// use fraktals::{Fractal, GenerationControl, MultibrotFormula, PixelGrid, QuadraticFormula};
+/// use networking::FragmentTask;
+///
+/// fn scalar<F: EscapeTimeFormula>(formula: F, task: &FragmentTask) -> Vec<(u32, u32)> {
+///     let escape_time = EscapeTime::new(formula, Plane::Parameter { z0: None })
+///         .with_interior_detection(task.options.interior_detection);
+///     PixelGrid::new(task)
+///         .points()
+///         .map(|pixel| {
+///             let orbit = escape_time.escape(pixel, task.max_iteration);
+///             let zn = orbit.z.square_norm() as f32 / 4.0;
+///             let count = orbit.count as f32 / task.max_iteration as f32;
+///             (zn.to_bits(), count.to_bits())
+///         })
+///         .collect()
+/// }
+///
+/// let range = Range { min: Point { x: -1.6, y: -1.1 }, max: Point { x: 0.6, y: 1.1 } };
+/// let resolution = Resolution { nx: 61, ny: 47 };
+/// for interior_detection in [false, true] {
+///     let mandelbrot = FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None });
+///     let multibrot = FractalDescriptor::Multibrot(MultibrotDescriptor { exponent: 3, plane: None });
+///     let cases: [(&dyn Fractal, FractalDescriptor); 2] =
+///         [(&FractalMandelbrot::new(), mandelbrot), (&FractalMultibrot::new(), multibrot)];
+///     for (fractal, descriptor) in cases {
+///         let mut task = FragmentTask::new(U8Data { offset: 0, count: 0 }, 200, resolution, range.clone(), descriptor.clone());
+///         task.options.interior_detection = interior_detection;
+///         let expected = match descriptor {
+///             FractalDescriptor::Mandelbrot(_) => scalar(QuadraticFormula { divergence_threshold_square: 4.0 }, &task),
+///             _ => scalar(MultibrotFormula { exponent: 3 }, &task),
+///         };
+///         let pixels = generate_parallel(fractal, &task, &descriptor, 8, &GenerationControl::new()).unwrap();
+///         let pixels: Vec<_> = pixels.iter().map(|pixel| (pixel.zn.to_bits(), pixel.count.to_bits())).collect();
+///         assert_eq!(pixels, expected);
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Cancelled`] as soon as `control` stops the generation.
+pub fn generate_parallel(
+    fractal: &dyn Fractal,
+    task: &FragmentTask,
+    descriptor: &FractalDescriptor,
+    threads: usize,
+    control: &GenerationControl,
+) -> Result<Vec<PixelIntensity>, Cancelled> {
+    let ny = task.resolution.ny as usize;
+    let threads = threads.clamp(1, ny.max(1));
+    if threads == 1 {
+        if control.is_passive() {
+            return Ok(fractal.generate(task, descriptor));
+        }
+        return fractal.generate_controlled(task, descriptor, control);
+    }
+
+    let band_height = band_height(task, threads, control);
+    run_bands(ny.div_ceil(band_height), threads, |band| {
+        control.check()?;
+        let start = band * band_height;
+        let end = (start + band_height).min(ny);
+        Ok(fractal.generate_reported_rows(task, descriptor, start..end, control))
+    })
+}
+
+/// Generates the typed records of the fragment described by `task` like
+/// [`Fractal::generate_records`], using up to `threads` threads.
+///
+/// Records keep the exact iteration counts, which the normalized `f32` counts of the
+/// intensities no longer tell apart past about 2^24 iterations. Bands are computed by
+/// [`Fractal::generate_record_rows`], so the records are identical to the single-threaded ones:
+///
+/// ```
+/// use common::{FractalDescriptor, MandelbrotDescriptor, Point, Range, Resolution, U8Data};
+/// use fraktals::{generate_records_parallel, Fractal, FractalMandelbrot, GenerationControl};
+/// use networking::FragmentTask;
+///
+/// let mut task = FragmentTask::new(
+///     U8Data { offset: 0, count: 0 },
+///     500,
+///     Resolution { nx: 40, ny: 30 },
+///     Range { min: Point { x: -2.2, y: -1.2 }, max: Point { x: 0.8, y: 1.2 } },
+///     FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None }),
+/// );
+/// task.options.interior_detection = true;
+/// let fractal = FractalMandelbrot::new();
+/// let records = generate_records_parallel(&fractal, &task, &task.fractal, 4, &GenerationControl::new()).unwrap();
+/// let expected = fractal.generate_records(&task, &task.fractal);
+/// assert_eq!(records.len(), expected.len());
+/// for (record, expected) in records.iter().zip(&expected) {
+///     assert_eq!((record.count, record.period), (expected.count, expected.period));
+///     assert_eq!(record.z.map(|z| (z.re.to_bits(), z.im.to_bits())), expected.z.map(|z| (z.re.to_bits(), z.im.to_bits())));
+/// }
+/// assert!(records.iter().any(|record| record.period.is_some()));
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Cancelled`] as soon as `control` stops the generation.
+pub fn generate_records_parallel(
+    fractal: &dyn Fractal,
+    task: &FragmentTask,
+    descriptor: &FractalDescriptor,
+    threads: usize,
+    control: &GenerationControl,
+) -> Result<Vec<PixelRecord>, Cancelled> {
+    let ny = task.resolution.ny as usize;
+    let threads = threads.clamp(1, ny.max(1));
+    let band_height = band_height(task, threads, control);
+    run_bands(ny.div_ceil(band_height), threads, |band| {
+        control.check()?;
+        let start = band * band_height;
+        let end = (start + band_height).min(ny);
+        let records = fractal.generate_record_rows(task, descriptor, start..end);
+        control.report_records(end - start, &records);
+        Ok(records)
+    })
+}
+
+/// Generates the fragment described by `task` using up to `threads` threads, and writes
+/// it to `sink` band by band, in row order.
+///
+/// Bands are cut as in [`generate_parallel`] for a generation that can be stopped, at
+/// most [`CONTROLLED_BAND_PIXELS`] pixels each, so that subdivision and deep-zoom series
+/// work on large bands. Threads compute at most [`BANDS_PER_THREAD`] bands each ahead of
+/// the last band written, so memory use stays flat whatever the size of the fragment.
+///
+/// The streamed pixels are those of [`generate_parallel`], and a cancellation stops the
+/// stream in the middle, right after the band being written:
+///
+/// ```
+/// use std::io;
+/// use common::{FractalDescriptor, MandelbrotDescriptor, PixelIntensity, PixelSink, Point, Range, Resolution, U8Data};
+/// use fraktals::{generate_parallel, stream_parallel, CancellationToken, Cancelled, Fractal, FractalMandelbrot, GenerationControl};
+/// use networking::FragmentTask;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// /// Cancels `token` once the first band is written.
+/// struct CancellingSink<'a> {
+///     token: &'a CancellationToken,
+///     pixels: Vec<PixelIntensity>,
+/// }
+///
+/// impl PixelSink for CancellingSink<'_> {
+///     fn write_pixels(&mut self, pixels: &[PixelIntensity]) -> io::Result<()> {
+///         self.token.cancel();
+///         self.pixels.write_pixels(pixels)
+///     }
+/// }
+///
+/// let task = FragmentTask::new(
+///     U8Data { offset: 0, count: 0 },
+///     200,
+///     Resolution { nx: 40, ny: 300 },
+///     Range { min: Point { x: -2.0, y: -1.2 }, max: Point { x: 1.0, y: 1.2 } },
+///     FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None }),
+/// );
+/// let fractal = FractalMandelbrot::new();
+/// let control = GenerationControl::new();
+/// let expected = generate_parallel(&fractal, &task, &task.fractal, 1, &control).unwrap();
+/// let bits = |pixels: &[PixelIntensity]| -> Vec<_> {
+///     pixels.iter().map(|pixel| (pixel.zn.to_bits(), pixel.count.to_bits())).collect()
+/// };
+///
+/// for threads in 1..=8 {
+///     let mut streamed = Vec::new();
+///     stream_parallel(&fractal, &task, &task.fractal, threads, &control, &mut streamed).unwrap();
+///     assert_eq!(bits(&streamed), bits(&expected), "{threads} threads");
+///
+///     let token = CancellationToken::new();
+///     let cancelling = GenerationControl::new().with_cancellation(&token);
+///     let mut sink = CancellingSink { token: &token, pixels: Vec::new() };
+///     let error = stream_parallel(&fractal, &task, &task.fractal, threads, &cancelling, &mut sink).unwrap_err();
+///     assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+///     assert!(Cancelled::is_cause_of(&error));
+///     assert!(!sink.pixels.is_empty() && sink.pixels.len() < expected.len());
+///     assert_eq!(bits(&sink.pixels), bits(&expected[..sink.pixels.len()]));
+/// }
+///
+/// /// Records the height of the tallest band it is asked for.
+/// struct BandProbe(AtomicUsize);
+///
+/// impl Fractal for BandProbe {
+///     fn generate_rows(&self, task: &FragmentTask, _: &FractalDescriptor, rows: std::ops::Range<usize>) -> Vec<PixelIntensity> {
+///         self.0.fetch_max(rows.len(), Ordering::Relaxed);
+///         vec![PixelIntensity::new(0.0, 0.0); rows.len() * task.resolution.nx as usize]
+///     }
+/// }
+///
+/// let probe = BandProbe(AtomicUsize::new(0));
+/// stream_parallel(&probe, &task, &task.fractal, 2, &control, &mut Vec::<PixelIntensity>::new()).unwrap();
+/// assert_eq!(probe.0.into_inner(), 300usize.div_ceil(2 * 4));
+/// ```
+///
+/// # Errors
+///
+/// Returns the first error of `sink`, or an [`io::ErrorKind::Interrupted`] error
+/// converted from [`Cancelled`] as soon as `control` stops the generation. The pixels
+/// written before are left in `sink`.
+pub fn stream_parallel(
+    fractal: &dyn Fractal,
+    task: &FragmentTask,
+    descriptor: &FractalDescriptor,
+    threads: usize,
+    control: &GenerationControl,
+    sink: &mut dyn PixelSink,
+) -> io::Result<()> {
+    let ny = task.resolution.ny as usize;
+    let threads = threads.clamp(1, ny.max(1));
+    let band_height = stream_band_height(task, threads);
+    let band_count = ny.div_ceil(band_height);
+    let compute = |band: usize| {
+        control.check()?;
+        let start = band * band_height;
+        let end = (start + band_height).min(ny);
+        Ok::<_, Cancelled>(fractal.generate_reported_rows(task, descriptor, start..end, control))
+    };
+
+    if threads == 1 {
+        for band in 0..band_count {
+            sink.write_pixels(&compute(band)?)?;
+        }
+        return Ok(());
+    }
+
+    // Number of bands written so far, or `None` once the writer has given up.
+    let written = Mutex::new(Some(0));
+    let progressed = Condvar::new();
+    let next_band = AtomicUsize::new(0);
+    let window = threads * BANDS_PER_THREAD;
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let (written, progressed, next_band, compute) = (&written, &progressed, &next_band, &compute);
+            scope.spawn(move || loop {
+                let band = next_band.fetch_add(1, Ordering::Relaxed);
+                if band >= band_count {
+                    break;
+                }
+                let mut state = written.lock().expect("stream state lock poisoned");
+                while state.is_some_and(|written| band >= written + window) {
+                    state = progressed.wait(state).expect("stream state lock poisoned");
+                }
+                if state.is_none() {
+                    break;
+                }
+                drop(state);
+
+                let computed = compute(band);
+                let cancelled = computed.is_err();
+                if sender.send((band, computed)).is_err() || cancelled {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_written = 0;
+        let outcome = receiver.iter().try_for_each(|(band, computed)| {
+            pending.insert(band, computed?);
+            while let Some(pixels) = pending.remove(&next_written) {
+                sink.write_pixels(&pixels)?;
+                control.check()?;
+                next_written += 1;
+                *written.lock().expect("stream state lock poisoned") = Some(next_written);
+                progressed.notify_all();
+            }
+            Ok(())
+        });
+
+        if outcome.is_err() {
+            *written.lock().expect("stream state lock poisoned") = None;
+            progressed.notify_all();
+        }
+        outcome
+    })
+}
+
+/// Generates the pixels of the fragment at the given row-major `indices`, through
+/// [`Fractal::generate_pixels`], using up to `threads` threads.
+///
+/// # Errors
+///
+/// Returns [`Cancelled`] as soon as `control` stops the generation.
+pub fn generate_pixels_parallel(
+    fractal: &dyn Fractal,
+    task: &FragmentTask,
+    descriptor: &FractalDescriptor,
+    indices: &[usize],
+    threads: usize,
+    control: &GenerationControl,
+) -> Result<Vec<PixelIntensity>, Cancelled> {
+    let threads = threads.clamp(1, indices.len().max(1));
+    if threads == 1 && control.is_passive() {
+        return Ok(fractal.generate_pixels(task, descriptor, indices));
+    }
+
+    let band_length = band_length(indices.len(), threads, control);
+    run_bands(indices.len().div_ceil(band_length), threads, |band| {
+        control.check()?;
+        let start = band * band_length;
+        let end = (start + band_length).min(indices.len());
+        Ok(fractal.generate_reported_pixels(task, descriptor, &indices[start..end], control))
+    })
+}
+
+/// Generates the typed records of the pixels of the fragment at the given row-major
+/// `indices`, through [`Fractal::generate_record_pixels`], using up to `threads` threads.
+///
+/// # Errors
+///
+/// Returns [`Cancelled`] as soon as `control` stops the generation.
+pub fn generate_record_pixels_parallel(
+    fractal: &dyn Fractal,
+    task: &FragmentTask,
+    descriptor: &FractalDescriptor,
+    indices: &[usize],
+    threads: usize,
+    control: &GenerationControl,
+) -> Result<Vec<PixelRecord>, Cancelled> {
+    let threads = threads.clamp(1, indices.len().max(1));
+    let band_length = band_length(indices.len(), threads, control);
+    run_bands(indices.len().div_ceil(band_length), threads, |band| {
+        control.check()?;
+        let start = band * band_length;
+        let end = (start + band_length).min(indices.len());
+        let records = fractal.generate_record_pixels(task, descriptor, &indices[start..end]);
+        control.report_records(0, &records);
+        Ok(records)
+    })
+}
+
+/// Height of the bands of rows of `task` shared by `threads` threads.
+fn band_height(task: &FragmentTask, threads: usize, control: &GenerationControl) -> usize {
+    let band_height = (task.resolution.ny as usize).div_ceil(threads * BANDS_PER_THREAD).max(1);
+    if control.can_stop() {
+        band_height.min(controlled_band_height(task))
+    } else {
+        band_height
+    }
+}
+
+/// Height of the bands of rows of `task` streamed by `threads` threads: those of a
+/// generation that can be stopped, whose size also bounds the pixels computed ahead.
+fn stream_band_height(task: &FragmentTask, threads: usize) -> usize {
+    let band_height = (task.resolution.ny as usize).div_ceil(threads * BANDS_PER_THREAD).max(1);
+    band_height.min(controlled_band_height(task))
+}
+
+/// Number of pixels of the bands of `pixel_count` scattered pixels shared by `threads`
+/// threads.
+fn band_length(pixel_count: usize, threads: usize, control: &GenerationControl) -> usize {
+    let band_length = pixel_count.div_ceil(threads * BANDS_PER_THREAD).max(1);
+    if control.can_stop() {
+        band_length.min(CONTROLLED_BAND_PIXELS)
+    } else {
+        band_length
+    }
+}
+
+/// Computes `band_count` bands on a pool of `threads` scoped threads and concatenates
+/// them in band order, stopping every thread at the first band cancelled.
+fn run_bands<T: Send>(
+    band_count: usize,
+    threads: usize,
+    compute: impl Fn(usize) -> Result<Vec<T>, Cancelled> + Sync,
+) -> Result<Vec<T>, Cancelled> {
+    let next_band = AtomicUsize::new(0);
+
+    let mut bands: Vec<(usize, Vec<T>)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut computed = Vec::new();
+                    loop {
+                        let band = next_band.fetch_add(1, Ordering::Relaxed);
+                        if band >= band_count {
+                            break;
+                        }
+                        match compute(band) {
+                            Ok(pixels) => computed.push((band, pixels)),
+                            Err(cancelled) => {
+                                next_band.store(band_count, Ordering::Relaxed);
+                                return Err(cancelled);
+                            }
+                        }
+                    }
+                    Ok(computed)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("fractal generation thread panicked"))
+            .collect::<Result<Vec<_>, _>>()
+            .map(|computed| computed.into_iter().flatten().collect())
+    })?;
+
+    bands.sort_by_key(|(band, _)| *band);
+    Ok(bands.into_iter().flat_map(|(_, pixels)| pixels).collect())
+}
@@ -0,0 +1,169 @@
+//! Perturbation theory for deep zooms into the Mandelbrot set.
+//!
+//! A single reference orbit `Z_n` is computed at the center of the view. Every pixel
+//! `C + δc` is then iterated as a small difference `δ_n = z_n - Z_n` obeying
+//!
+//! `δ_{n+1} = (2 Z_n + δ_n) δ_n + δc`,
+//!
+//! which stays accurate in `f64` however tiny `δc` gets. The first iterations are
+//! skipped with a truncated series in `δc`, and pixels whose orbit loses precision
+//! against the reference are rebased onto it.
+
+use common::{BigComplex, Complex, ComplexTrait};
+
+/// Pauldelbrot's glitch criterion: an orbit passing this close to zero compared with
+/// the reference (squared ratio) has lost the precision of its difference.
+const GLITCH_TOLERANCE_SQUARE: f64 = 1e-6;
+
+/// Maximum relative error tolerated on a pixel when skipping iterations with the series.
+const SERIES_TOLERANCE: f64 = 1e-3;
+
+/// Orbit of the reference point at the center of the view.
+pub struct ReferenceOrbit {
+    points: Vec<Complex>,
+}
+
+impl ReferenceOrbit {
+    /// Computes the orbit of the center given as decimal strings, until it escapes or
+    /// reaches `max_iteration`.
+    ///
+    /// The orbit is iterated with `precision` mantissa bits and each value is rounded
+    /// to `f64` once computed, so the location of the view is exact however deep it is.
+    ///
+    /// # Errors
+    /// Returns an error if a coordinate is not a valid decimal number.
+    pub fn compute(
+        center_re: &str,
+        center_im: &str,
+        precision: u32,
+        max_iteration: u32,
+    ) -> Result<Self, String> {
+        let center = BigComplex::parse(center_re, center_im, precision)?;
+
+        let mut points = vec![Complex::new(0.0, 0.0)];
+        let mut z = BigComplex::zero(precision);
+        while points.len() <= max_iteration as usize && points[points.len() - 1].square_norm() < 4.0 {
+            z = z.square().add(&center);
+            points.push(z.to_complex());
+        }
+
+        Ok(ReferenceOrbit { points })
+    }
+
+    /// Mantissa bits needed for a reference orbit whose view has the given pixel spacing:
+    /// enough to resolve the spacing relative to the `[-2, 2]` range of the set, with
+    /// margin for the rounding accumulated along the orbit.
+    pub fn precision_for(pixel_spacing: f64) -> u32 {
+        const GUARD_BITS: u32 = 64;
+        let depth = (2.0 / pixel_spacing).log2().ceil();
+        GUARD_BITS + if depth.is_finite() && depth > 0.0 { depth as u32 } else { 0 }
+    }
+
+    /// Values `Z_0, Z_1, …` of the reference orbit, the last one possibly escaped.
+    pub fn points(&self) -> &[Complex] {
+        &self.points
+    }
+}
+
+/// Truncated series `δ_n ≈ A_n δc + B_n δc² + C_n δc³` used to skip the first iterations.
+pub struct SeriesApproximation {
+    skipped: u32,
+    a: Complex,
+    b: Complex,
+    c: Complex,
+}
+
+impl SeriesApproximation {
+    /// Advances the series coefficients along `reference` for as long as the cubic term
+    /// stays negligible for every offset up to `max_delta`, compared with `pixel_spacing`.
+    pub fn new(reference: &ReferenceOrbit, max_delta: f64, pixel_spacing: f64) -> Self {
+        let zero = Complex::new(0.0, 0.0);
+        let mut series = SeriesApproximation {
+            skipped: 0,
+            a: zero,
+            b: zero,
+            c: zero,
+        };
+
+        let points = reference.points();
+        let two = Complex::new(2.0, 0.0);
+        for (n, z) in points.iter().enumerate().take(points.len().saturating_sub(2)) {
+            let two_z = z.multiply(&two);
+            let a = two_z.multiply(&series.a).add(&Complex::new(1.0, 0.0));
+            let b = two_z.multiply(&series.b).add(&series.a.multiply(&series.a));
+            let c = two_z
+                .multiply(&series.c)
+                .add(&series.a.multiply(&series.b).multiply(&two));
+
+            let error = c.square_norm().sqrt() * max_delta.powi(3);
+            let tolerance = SERIES_TOLERANCE * a.square_norm().sqrt() * pixel_spacing;
+            if error.is_nan() || error > tolerance {
+                break;
+            }
+
+            series = SeriesApproximation {
+                skipped: n as u32 + 1,
+                a,
+                b,
+                c,
+            };
+        }
+
+        series
+    }
+
+    /// Number of iterations replaced by the series.
+    pub fn skipped(&self) -> u32 {
+        self.skipped
+    }
+
+    /// Returns the difference `δ_n` after the skipped iterations for the offset `delta_c`.
+    pub fn delta(&self, delta_c: &Complex) -> Complex {
+        let delta_c2 = delta_c.multiply(delta_c);
+        let delta_c3 = delta_c2.multiply(delta_c);
+        self.a
+            .multiply(delta_c)
+            .add(&self.b.multiply(&delta_c2))
+            .add(&self.c.multiply(&delta_c3))
+    }
+}
+
+/// Iterates the pixel at offset `delta_c` from the reference, until it escapes or
+/// reaches `max_iteration`.
+///
+/// Returns the last value of the orbit and the number of iterations performed.
+pub fn perturbed_escape(
+    reference: &ReferenceOrbit,
+    series: &SeriesApproximation,
+    delta_c: &Complex,
+    max_iteration: u32,
+) -> (Complex, u32) {
+    let points = reference.points();
+    let mut m = series.skipped() as usize;
+    let mut count = series.skipped().min(max_iteration);
+    let mut delta = series.delta(delta_c);
+    let mut z = points[m].add(&delta);
+
+    while z.square_norm() < 4.0 && count < max_iteration {
+        if m + 1 >= points.len() {
+            // End of the reference orbit: continue from its start, where Z_0 = 0.
+            delta = z;
+            m = 0;
+        }
+
+        delta = points[m].multiply(&Complex::new(2.0, 0.0)).add(&delta).multiply(&delta).add(delta_c);
+        m += 1;
+        count += 1;
+        z = points[m].add(&delta);
+
+        // Rebase when the orbit gets closer to zero than to the reference, or when the
+        // glitch criterion shows that the difference no longer carries enough precision.
+        let norm = z.square_norm();
+        if norm < delta.square_norm() || norm < GLITCH_TOLERANCE_SQUARE * points[m].square_norm() {
+            delta = z;
+            m = 0;
+        }
+    }
+
+    (z, count)
+}
@@ -0,0 +1,39 @@
+//! Automatic choice of the arithmetic from the depth of a view.
+
+use common::Precision;
+use networking::FragmentTask;
+use crate::grid::PixelGrid;
+
+/// Ratio kept between the epsilon of an arithmetic and the relative pixel spacing, so
+/// that the rounding errors accumulated along the orbits stay below one pixel.
+const ROUNDING_MARGIN: f64 = 64.0;
+
+/// Smallest coordinate magnitude considered: whatever the view, orbits wander in a disk
+/// of radius about 2 before escaping.
+const MIN_MAGNITUDE: f64 = 2.0;
+
+/// Pixel spacing of `task` relative to the magnitude of its coordinates.
+pub fn relative_pixel_spacing(task: &FragmentTask) -> f64 {
+    let grid = PixelGrid::new(task);
+    let range = &task.range;
+    let magnitude = [range.min.x, range.max.x, range.min.y, range.max.y]
+        .iter()
+        .map(|coordinate| coordinate.abs())
+        .fold(MIN_MAGNITUDE, f64::max);
+
+    grid.x_step.min(grid.y_step) / magnitude
+}
+
+/// Returns the cheapest arithmetic that still resolves distinct pixels in `task`: `f64`
+/// for overviews and usual zooms, double-double beyond.
+///
+/// `f32` is never selected: the batched kernels iterate it no faster than `f64`, as
+/// `cargo bench -p fraktals --bench precision` shows. Views too deep even for
+/// double-double still get it, the most accurate available.
+pub fn select_precision(task: &FragmentTask) -> Precision {
+    let spacing = relative_pixel_spacing(task);
+    [Precision::Double, Precision::DoubleDouble]
+        .into_iter()
+        .find(|precision| precision.epsilon() * ROUNDING_MARGIN <= spacing)
+        .unwrap_or(Precision::DoubleDouble)
+}
@@ -0,0 +1,109 @@
+//! Progressive multi-pass generation of a fragment.
+//!
+//! The fragment is computed in interleaved passes: one pixel out of 16 first (every
+//! fourth pixel of every fourth row), then one out of 4, then the remaining ones. Pixels
+//! computed by a pass are kept by the following ones, so the whole fragment costs the
+//! same as a single pass. After each coarse pass, a preview of the full fragment, with
+//! each sample repeated over the block it stands for, is handed to a callback.
+
+use common::{FractalDescriptor, PixelIntensity};
+use networking::FragmentTask;
+use crate::control::{Cancelled, GenerationControl};
+use crate::fraktal::Fractal;
+use crate::parallel::generate_pixels_parallel;
+
+/// Spacing, in pixels along both axes, between the samples of each pass.
+const PASS_STRIDES: [usize; 3] = [4, 2, 1];
+
+/// Intermediate result of a progressive generation.
+pub struct ProgressivePass<'a> {
+    /// Spacing between the samples computed so far: every block of `stride × stride`
+    /// pixels repeats the sample at its top-left corner.
+    pub stride: usize,
+    /// Preview of the whole fragment, row by row.
+    pub pixels: &'a [PixelIntensity],
+}
+
+/// Generates the fragment of `task` in progressively finer passes, using up to `threads`
+/// threads, and calls `on_preview` after each pass but the last.
+///
+/// Pixels are computed one by one through [`Fractal::generate_pixels`], so subdivision
+/// does not apply: the last pass returns the pixels [`crate::generate_parallel`] computes
+/// with `subdivision` off.
+///
+/// ```
+/// use common::{FractalDescriptor, MandelbrotDescriptor, Point, Range, Resolution, U8Data};
+/// use fraktals::{generate_parallel, generate_progressive, FractalMandelbrot, GenerationControl};
+/// use networking::FragmentTask;
+///
+/// let mut task = FragmentTask::new(
+///     U8Data { offset: 0, count: 0 },
+///     300,
+///     Resolution { nx: 67, ny: 45 },
+///     Range { min: Point { x: -2.0, y: -1.2 }, max: Point { x: 1.0, y: 1.2 } },
+///     FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None }),
+/// );
+/// task.options.interior_detection = true;
+/// let fractal = FractalMandelbrot::new();
+/// let control = GenerationControl::new();
+/// let expected = generate_parallel(&fractal, &task, &task.fractal, 4, &control).unwrap();
+///
+/// for subdivision in [false, true] {
+///     task.options.subdivision = subdivision;
+///     let mut strides = Vec::new();
+///     let pixels = generate_progressive(&fractal, &task, &task.fractal, 4, &control, |pass| strides.push(pass.stride))
+///         .unwrap();
+///     assert_eq!(strides, [4, 2]);
+///     assert_eq!(pixels.len(), expected.len());
+///     for (pixel, expected) in pixels.iter().zip(&expected) {
+///         assert_eq!((pixel.zn.to_bits(), pixel.count.to_bits()), (expected.zn.to_bits(), expected.count.to_bits()));
+///     }
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`Cancelled`] as soon as `control` stops the generation.
+pub fn generate_progressive(
+    fractal: &dyn Fractal,
+    task: &FragmentTask,
+    descriptor: &FractalDescriptor,
+    threads: usize,
+    control: &GenerationControl,
+    mut on_preview: impl FnMut(&ProgressivePass),
+) -> Result<Vec<PixelIntensity>, Cancelled> {
+    let nx = task.resolution.nx as usize;
+    let ny = task.resolution.ny as usize;
+    let mut pixels = vec![PixelIntensity::new(0.0, 0.0); nx * ny];
+    let mut previous_stride = None;
+
+    for stride in PASS_STRIDES {
+        let indices: Vec<usize> = (0..ny)
+            .step_by(stride)
+            .flat_map(|j| (0..nx).step_by(stride).map(move |i| j * nx + i))
+            .filter(|index| !previous_stride.is_some_and(|previous| on_grid(*index, nx, previous)))
+            .collect();
+        let computed = generate_pixels_parallel(fractal, task, descriptor, &indices, threads, control)?;
+        for (index, pixel) in indices.into_iter().zip(computed) {
+            pixels[index] = pixel;
+        }
+        previous_stride = Some(stride);
+
+        if stride > 1 {
+            let preview: Vec<PixelIntensity> = (0..nx * ny)
+                .map(|index| {
+                    let (i, j) = (index % nx, index / nx);
+                    pixels[(j - j % stride) * nx + i - i % stride]
+                })
+                .collect();
+            on_preview(&ProgressivePass { stride, pixels: &preview });
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Tells whether the pixel at `index` was sampled by a pass of the given `stride`.
+fn on_grid(index: usize, nx: usize, stride: usize) -> bool {
+    (index % nx).is_multiple_of(stride) && (index / nx).is_multiple_of(stride)
+}
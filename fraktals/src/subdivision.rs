@@ -0,0 +1,160 @@
+//! Mariani–Silver rectangle subdivision.
+//!
+//! Instead of iterating every pixel, the border of a rectangle is computed first. When
+//! every border pixel has the same iteration count and period the whole rectangle is
+//! filled with them, otherwise the rectangle is split in two and both halves are
+//! processed the same way. Since the Mandelbrot set is connected, a uniform border
+//! rarely hides any detail, and large interior or exterior regions are filled after
+//! computing only their outline.
+//! Filled pixels are not iterated, so they have no last value: their records hold no `z`
+//! and the fractals send a `zn` of `0` for them.
+//!
+//! # Example
+//!
+//! Subdivision is checked against the brute-force rendering of the same fragment: computed
+//! pixels are identical, filled ones only differ when a uniform border hid some detail.
+//!
+//! ```
+//! use common::{ComplexTrait, FractalDescriptor, MandelbrotDescriptor, Point, Range, Resolution, U8Data};
+//! use fraktals::{Fractal, FractalMandelbrot};
+//! use networking::FragmentTask;
+//!
+//! let mut task = FragmentTask::new(
+//!     U8Data { offset: 0, count: 0 },
+//!     256,
+//!     Resolution { nx: 200, ny: 150 },
+//!     Range { min: Point { x: -2.0, y: -1.2 }, max: Point { x: 1.0, y: 1.2 } },
+//!     FractalDescriptor::Mandelbrot(MandelbrotDescriptor { plane: None }),
+//! );
+//! let brute_force = FractalMandelbrot::new().generate(&task, &task.fractal);
+//!
+//! task.options.subdivision = true;
+//! let subdivided = FractalMandelbrot::new().generate(&task, &task.fractal);
+//! let records = FractalMandelbrot::new().generate_records(&task, &task.fractal);
+//! assert_eq!(brute_force.len(), subdivided.len());
+//!
+//! let (mut filled, mut mismatches) = (0, 0);
+//! for ((expected, actual), record) in brute_force.iter().zip(&subdivided).zip(&records) {
+//!     match record.z {
+//!         Some(z) => {
+//!             assert_eq!(actual.zn.to_bits(), expected.zn.to_bits());
+//!             assert_eq!(actual.zn, z.square_norm() as f32 / 4.0);
+//!             assert_eq!(actual.count.to_bits(), expected.count.to_bits());
+//!         }
+//!         None => {
+//!             filled += 1;
+//!             assert_eq!(actual.zn, 0.0);
+//!             mismatches += (actual.count != expected.count) as usize;
+//!         }
+//!     }
+//! }
+//! assert!(filled * 4 > brute_force.len(), "only {filled} pixels filled");
+//! assert!(mismatches * 1000 < brute_force.len(), "{mismatches} pixels differ");
+//! ```
+//!
+//! Julia sets and the iterated sin(z) subdivide the same way:
+//!
+//! ```
+//! use common::{Complex, ComplexTrait, FractalDescriptor, IteratedSinZDescriptor, JuliaDescriptor, Point, Range, Resolution, U8Data};
+//! use fraktals::{Fractal, FractalJulia, IteratedSinZ};
+//! use networking::FragmentTask;
+//!
+//! let c = Complex::new(-0.8, 0.156);
+//! let julia = FractalDescriptor::Julia(JuliaDescriptor { c, divergence_threshold_square: 4.0, plane: None });
+//! let sin_c = Complex::new(1.0, 0.1);
+//! let sin = FractalDescriptor::IteratedSinZ(IteratedSinZDescriptor { c: sin_c, plane: None });
+//! let cases: [(&dyn Fractal, FractalDescriptor); 2] = [(&FractalJulia::new(), julia), (&IteratedSinZ::new(sin_c), sin)];
+//! for (fractal, descriptor) in cases {
+//!     let mut task = FragmentTask::new(
+//!         U8Data { offset: 0, count: 0 },
+//!         256,
+//!         Resolution { nx: 200, ny: 150 },
+//!         Range { min: Point { x: -2.0, y: -1.5 }, max: Point { x: 2.0, y: 1.5 } },
+//!         descriptor.clone(),
+//!     );
+//!     task.options.subdivision = true;
+//!     let records = fractal.generate_records(&task, &descriptor);
+//!     let filled = records.iter().filter(|record| record.z.is_none()).count();
+//!     assert!(filled * 10 > records.len(), "{descriptor:?}: only {filled} pixels filled");
+//! }
+//! ```
+
+use std::ops::Range;
+
+use common::{Complex, Float};
+use crate::escape_time::Orbit;
+use crate::grid::PixelGrid;
+
+/// Rectangles with a side shorter than this are computed pixel by pixel.
+const MIN_SUBDIVIDED_SIDE: usize = 4;
+
+/// Computes the given rows of `grid` by Mariani–Silver subdivision.
+///
+/// `evaluate` computes the orbit of a single pixel from its plane coordinates in the
+/// arithmetic `T`. Filled pixels copy the count and period of a border pixel, and are
+/// marked [`Orbit::filled`].
+pub fn generate_subdivided<T: Float>(
+    grid: &PixelGrid,
+    rows: Range<usize>,
+    evaluate: impl Fn(Complex<T>) -> Orbit,
+) -> Vec<Orbit> {
+    let width = grid.nx;
+    let height = rows.len();
+    let mut pixels: Vec<Option<Orbit>> = vec![None; width * height];
+
+    let compute = |pixels: &mut [Option<Orbit>], i: usize, j: usize| {
+        let index = j * width + i;
+        *pixels[index].get_or_insert_with(|| evaluate(grid.point_at(i, rows.start + j)))
+    };
+
+    // Rectangles as inclusive pixel bounds (i0, j0, i1, j1).
+    let mut rectangles = Vec::new();
+    if width > 0 && height > 0 {
+        rectangles.push((0, 0, width - 1, height - 1));
+    }
+
+    while let Some((i0, j0, i1, j1)) = rectangles.pop() {
+        if i1 - i0 < MIN_SUBDIVIDED_SIDE || j1 - j0 < MIN_SUBDIVIDED_SIDE {
+            for j in j0..=j1 {
+                for i in i0..=i1 {
+                    compute(&mut pixels, i, j);
+                }
+            }
+            continue;
+        }
+
+        let reference = compute(&mut pixels, i0, j0);
+        let matches = |orbit: Orbit| orbit.count == reference.count && orbit.period == reference.period;
+        let mut uniform = true;
+        for i in i0..=i1 {
+            uniform &= matches(compute(&mut pixels, i, j0));
+            uniform &= matches(compute(&mut pixels, i, j1));
+        }
+        for j in j0..=j1 {
+            uniform &= matches(compute(&mut pixels, i0, j));
+            uniform &= matches(compute(&mut pixels, i1, j));
+        }
+
+        if uniform {
+            let fill = Orbit { filled: true, ..reference };
+            for j in j0 + 1..j1 {
+                for i in i0 + 1..i1 {
+                    pixels[j * width + i].get_or_insert(fill);
+                }
+            }
+        } else if i1 - i0 >= j1 - j0 {
+            let middle = (i0 + i1) / 2;
+            rectangles.push((i0, j0, middle, j1));
+            rectangles.push((middle, j0, i1, j1));
+        } else {
+            let middle = (j0 + j1) / 2;
+            rectangles.push((i0, j0, i1, middle));
+            rectangles.push((i0, middle, i1, j1));
+        }
+    }
+
+    pixels
+        .into_iter()
+        .map(|pixel| pixel.expect("every pixel is computed or filled"))
+        .collect()
+}
@@ -21,6 +21,10 @@ pub struct FragmentResult {
     pub range: Range,
     /// Offset and count of the pixels in the final image buffer.
     pub pixels: PixelData,
+    /// Iteration limit the pixel counts are normalized by, when adaptive iterations raised
+    /// it above the `max_iteration` of the task. Absent otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iteration: Option<u32>,
 }
 
 /// Builder for creating a [`FragmentResult`] safely and progressively.
@@ -43,6 +47,7 @@ pub struct FragmentResultBuilder {
     resolution: Option<Resolution>,
     range: Option<Range>,
     pixels: Option<PixelData>,
+    max_iteration: Option<u32>,
 }
 
 impl FragmentResult {
@@ -53,9 +58,24 @@ impl FragmentResult {
             resolution: None,
             range: None,
             pixels: None,
+            max_iteration: None,
         }
     }
 
+    /// Returns the iteration limit the pixel counts of the fragment are normalized by,
+    /// `task_max_iteration` unless the worker reported another one.
+    ///
+    /// ```
+    /// use networking::FragmentResult;
+    ///
+    /// let builder = || FragmentResult::builder().with_id(0, 0).with_resolution(1, 1).with_range(0.0, 0.0, 1.0, 1.0).with_pixels(0, 1);
+    /// assert_eq!(builder().build().unwrap().effective_max_iteration(100), 100);
+    /// assert_eq!(builder().with_max_iteration(800).build().unwrap().effective_max_iteration(100), 800);
+    /// ```
+    pub fn effective_max_iteration(&self, task_max_iteration: u32) -> u32 {
+        self.max_iteration.unwrap_or(task_max_iteration)
+    }
+
     /// Serializes the result into a JSON string wrapped in a `FragmentResult` key.
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(&json!({ "FragmentResult": self }))
@@ -90,6 +110,13 @@ impl FragmentResultBuilder {
         self
     }
 
+    /// Sets the iteration limit the pixel counts are normalized by, when it differs from
+    /// the `max_iteration` of the task.
+    pub fn with_max_iteration(mut self, max_iteration: u32) -> Self {
+        self.max_iteration = Some(max_iteration);
+        self
+    }
+
     /// Builds the [`FragmentResult`] if all required fields are present.
     ///
     /// # Errors
@@ -105,6 +132,7 @@ impl FragmentResultBuilder {
             resolution,
             range,
             pixels,
+            max_iteration: self.max_iteration,
         })
     }
 }
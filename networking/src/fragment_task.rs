@@ -4,8 +4,10 @@
 //! a fragment of the final image, including resolution, complex plane range,
 //! maximum iteration, and the type of fractal to compute.
 
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
-use common::{Range, Resolution, U8Data, FractalDescriptor};
+use common::{Range, Resolution, U8Data, FractalDescriptor, RenderOptions};
 
 /// A computation task that defines a fragment of the fractal to be rendered.
 ///
@@ -16,8 +18,10 @@ use common::{Range, Resolution, U8Data, FractalDescriptor};
 /// - `max_iteration`: The maximum number of iterations to use in the fractal algorithm.
 /// - `resolution`: The width and height of the pixel grid for this fragment.
 /// - `range`: The coordinate range in the complex plane for this task.
+/// - `range_low`: Optional low-order parts of the range, for views narrower than `f64` allows.
 /// - `fractal`: The fractal type and its associated parameters.
-#[derive(Debug, Deserialize, Serialize)]
+/// - `options`: Optional rendering settings, defaulted when absent from the JSON.
+#[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct FragmentTask {
     /// Unique task identifier using offset and count (e.g., position in a buffer).
     #[serde(rename = "id")]
@@ -32,12 +36,22 @@ pub struct FragmentTask {
     /// Coordinate range of the complex plane for this fragment.
     pub range: Range,
 
+    /// Low-order parts of the range coordinates: each coordinate is the exact sum of its
+    /// values in `range` and `range_low`, as with double-double numbers. Only computations
+    /// wider than `f64` see the difference.
+    #[serde(default)]
+    pub range_low: Option<Range>,
+
     /// Fractal descriptor defining the type of fractal to generate and its parameters.
     pub fractal: FractalDescriptor,
+
+    /// Optional rendering settings for this fragment.
+    #[serde(default)]
+    pub options: RenderOptions,
 }
 
 impl FragmentTask {
-    /// Creates a new `FragmentTask` with all required parameters.
+    /// Creates a new `FragmentTask` with all required parameters and default options.
     ///
     /// # Arguments
     /// * `id` - The identifier for the fragment.
@@ -57,15 +71,17 @@ impl FragmentTask {
             max_iteration,
             resolution,
             range,
+            range_low: None,
             fractal,
+            options: RenderOptions::default(),
         }
     }
+}
 
-    /// Serializes the task into a JSON string.
-    ///
-    /// # Panics
-    /// Panics if serialization fails.
-    pub fn to_string(&self) -> String {
-        serde_json::to_string(self).expect("Failed to transform FragmentTask to JSON")
+impl fmt::Display for FragmentTask {
+    /// Formats the task as its JSON representation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
     }
 }
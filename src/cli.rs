@@ -33,4 +33,6 @@ fn print_help() {
     println!("\nAvailable Commands:");
     println!("  worker --help                 : Show this help menu");
     println!("  worker connect <ip:port>      : Connect to a fractal server");
+    println!("    --precision <precision>     : Force single, double or double-double arithmetic");
+    println!("                                  instead of choosing it from the depth of each task");
 }
@@ -1,15 +1,26 @@
 use std::net::Ipv4Addr;
 
+use common::Precision;
 use networking::FragmentRequest;
 use worker::Worker;
 use crate::cli::read_user_input;
 
 pub fn handle_connect_command(cmd: &str) -> Result<(), Box<dyn std::error::Error>> {
     let args = cmd.trim_start_matches("worker connect ").trim();
-    let parts: Vec<&str> = args.split(':').collect();
+    let (address, precision) = match args.split_once(" --precision ") {
+        Some((address, name)) => match name.trim().parse::<Precision>() {
+            Ok(precision) => (address.trim(), Some(precision)),
+            Err(e) => {
+                println!("{}", e);
+                return Ok(());
+            }
+        },
+        None => (args, None),
+    };
+    let parts: Vec<&str> = address.split(':').collect();
 
     if parts.len() != 2 {
-        println!("Invalid format. Use: worker connect <ip>:<port>");
+        println!("Invalid format. Use: worker connect <ip>:<port> [--precision <precision>]");
         return Ok(());
     }
 
@@ -21,10 +32,10 @@ pub fn handle_connect_command(cmd: &str) -> Result<(), Box<dyn std::error::Error
         return Ok(());
     }
 
-    start_worker(ip, port)
+    start_worker(ip, port, precision)
 }
 
-fn start_worker(ip: &str, port: &str) -> Result<(), Box<dyn std::error::Error>> {
+fn start_worker(ip: &str, port: &str, precision: Option<Precision>) -> Result<(), Box<dyn std::error::Error>> {
     let connection_name = read_user_input("Enter connection name: ");
     let workload = read_user_input("Enter max workload: ");
 
@@ -32,7 +43,9 @@ fn start_worker(ip: &str, port: &str) -> Result<(), Box<dyn std::error::Error>>
         .parse::<u32>()
         .map_err(|_| "Invalid number for workload")?;
 
-    let mut worker = Worker::new(ip.to_string(), connection_name.clone(), port.parse()?);
+    let mut worker = Worker::new(ip.to_string(), connection_name.clone(), port.parse()?)
+        .with_max_work_load(workload)
+        .with_precision(precision);
     let request = FragmentRequest::builder()
         .with_worker_name(connection_name)
         .with_max_work_load(workload)
@@ -7,8 +7,9 @@ use std::io::{self, Read, Write};
 use std::net::TcpStream;
 
 use serde_json::Value;
-use common::PixelIntensity;
+use common::{PixelIntensity, PixelSink, PixelWriter};
 use networking::{FragmentResult, FragmentTask};
+use crate::dispatcher::validate_task;
 
 /// A TCP connection wrapper that facilitates communication between a worker and the server.
 ///
@@ -63,7 +64,8 @@ impl Connection {
     /// A tuple of `FragmentTask` and a raw binary buffer (usually unused data or input).
     ///
     /// # Errors
-    /// Returns a string describing the failure cause (e.g., deserialization, I/O).
+    /// Returns a string describing the failure cause (e.g., deserialization, I/O, or a
+    /// task rejected by [`validate_task`]).
     pub fn read_task(&mut self) -> Result<(FragmentTask, Vec<u8>), String> {
         let mut total_size_buf = [0; 4];
         self.stream.read_exact(&mut total_size_buf).map_err(|e| e.to_string())?;
@@ -84,6 +86,7 @@ impl Connection {
 
         if let Some(task_value) = json_value.get("FragmentTask") {
             let task: FragmentTask = serde_json::from_value(task_value.clone()).map_err(|e| e.to_string())?;
+            validate_task(&task)?;
             Ok((task, data_buf))
         } else {
             Err("Missing FragmentTask field".to_string())
@@ -111,21 +114,57 @@ impl Connection {
         result: &FragmentResult,
         id: &[u8],
         pixels: &[PixelIntensity],
+    ) -> io::Result<()> {
+        self.send_result_streamed(result, id, pixels.len(), |sink| sink.write_pixels(pixels))
+    }
+
+    /// Sends a fractal computation result whose pixels are streamed while they are computed.
+    ///
+    /// The message has the same structure as with [`Connection::send_result`]. The
+    /// header is sent first, then `write_pixels` writes the pixels into a buffered
+    /// [`PixelSink`] over the connection.
+    ///
+    /// # Arguments
+    /// * `result` - The metadata describing the result (range, resolution, etc.).
+    /// * `id` - The raw task ID as byte slice.
+    /// * `pixel_count` - The number of pixels `write_pixels` writes, announced in the header.
+    /// * `write_pixels` - Computes the pixels and writes them to the sink.
+    ///
+    /// # Errors
+    /// Returns an error if sending fails at any point, if `write_pixels` fails, or if it
+    /// does not write `pixel_count` pixels. The message is then left incomplete, and the
+    /// connection should be dropped.
+    pub fn send_result_streamed(
+        &mut self,
+        result: &FragmentResult,
+        id: &[u8],
+        pixel_count: usize,
+        write_pixels: impl FnOnce(&mut dyn PixelSink) -> io::Result<()>,
     ) -> io::Result<()> {
         let json = result.to_json().expect("failed to serialize");
         let json_bytes = json.as_bytes();
         let json_len = json_bytes.len() as u32;
-        let total_len = json_len + (id.len() + pixels.len() * 8) as u32;
+        let total_len = json_len + (id.len() + pixel_count * 8) as u32;
 
         self.stream.write_all(&total_len.to_be_bytes())?;
         self.stream.write_all(&json_len.to_be_bytes())?;
         self.stream.write_all(json_bytes)?;
         self.stream.write_all(id)?;
 
-        for pixel in pixels {
-            self.stream.write_all(&pixel.zn.to_be_bytes())?;
-            self.stream.write_all(&pixel.count.to_be_bytes())?;
+        let mut writer = PixelWriter::new(&mut self.stream);
+        if let Err(e) = write_pixels(&mut writer) {
+            writer.abandon();
+            return Err(e);
+        }
+        let written = writer.written();
+        if written != pixel_count {
+            writer.abandon();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} pixels written, {} announced", written, pixel_count),
+            ));
         }
+        writer.finish()?;
 
         Ok(())
     }
@@ -5,6 +5,7 @@
 
 use common::FractalDescriptor;
 use fraktals::*;
+use networking::FragmentTask;
 
 /// Returns a boxed [`Fractal`] implementation corresponding to the given descriptor.
 ///
@@ -27,9 +28,29 @@ pub fn dispatch_fractal(descriptor: &FractalDescriptor) -> Box<dyn Fractal> {
     match descriptor {
         FractalDescriptor::Julia(_) => Box::new(FractalJulia::new()),
         FractalDescriptor::Mandelbrot(_) => Box::new(FractalMandelbrot::new()),
+        FractalDescriptor::MandelbrotDeepZoom(_) => Box::new(FractalMandelbrotDeepZoom::new()),
+        FractalDescriptor::Multibrot(_) => Box::new(FractalMultibrot::new()),
         FractalDescriptor::IteratedSinZ(desc) => Box::new(IteratedSinZ::new(desc.c)),
         FractalDescriptor::NewtonRaphsonZ3(_) => Box::new(NewtonRaphsonZ3Fractal::new()),
+        FractalDescriptor::NewtonRaphsonTranscendental(_) => {
+            Box::new(NewtonRaphsonTranscendentalFractal::new())
+        }
         // You can add more variants like NovaNewtonZ3, NewtonRaphsonZ4, etc. here
         _ => panic!("Fractal not implemented yet"),
     }
 }
+
+/// Checks that `task` can be computed before it is accepted, since the pixel count of its
+/// result is announced before they are generated.
+///
+/// # Errors
+/// Returns a description of the first problem found: render options that cannot be
+/// combined, options the fractal does not support, or a descriptor it cannot render.
+pub fn validate_task(task: &FragmentTask) -> Result<(), String> {
+    task.options.validate()?;
+    match task.fractal {
+        FractalDescriptor::MandelbrotDeepZoom(_) => FractalMandelbrotDeepZoom::validate(task),
+        FractalDescriptor::IteratedSinZ(_) => IteratedSinZ::validate(task),
+        _ => Ok(()),
+    }
+}
@@ -1,10 +1,38 @@
 //! Task processor module responsible for executing fractal computations and
 //! packaging results into a `FragmentResult`.
 
-use common::PixelIntensity;
+use std::io;
+
+use common::{PixelIntensity, PixelSink, Precision};
+use fraktals::{
+    generate_adaptive, generate_parallel, relative_pixel_spacing, select_precision, stream_parallel, supersample,
+    Cancelled, GenerationControl,
+};
 use networking::{FragmentResult, FragmentTask};
 use crate::dispatcher::dispatch_fractal;
 
+/// Chooses the arithmetic of `task`, records it in the task options and logs it.
+///
+/// The `forced` precision of the worker wins, then the one requested by the task;
+/// otherwise the cheapest arithmetic resolving the pixels of the task is selected.
+pub fn resolve_precision(task: &mut FragmentTask, forced: Option<Precision>) -> Precision {
+    let (precision, origin) = match (forced, task.options.precision) {
+        (Some(precision), _) => (precision, "forced by the worker"),
+        (None, Some(precision)) => (precision, "requested by the task"),
+        (None, None) => (select_precision(task), "selected automatically"),
+    };
+
+    println!(
+        "Task {}: {:?} precision {} (pixel spacing {:.1e} of the coordinates)",
+        task.id.offset,
+        precision,
+        origin,
+        relative_pixel_spacing(task),
+    );
+    task.options.precision = Some(precision);
+    precision
+}
+
 /// Processes a single [`FragmentTask`] by generating its associated pixel data
 /// using the specified fractal descriptor.
 ///
@@ -16,27 +44,88 @@ use crate::dispatcher::dispatch_fractal;
 /// - `task`: The task to process, which contains the fractal descriptor, resolution,
 ///   and coordinate range.
 /// - `_data_id`: Currently unused, but can be used for tracking or validation purposes.
+/// - `threads`: Number of threads sharing the computation of the fragment.
+/// - `control`: Cancellation and progress reporting of the computation.
 ///
 /// # Returns
 /// A tuple containing:
-/// - `FragmentResult`: Metadata about the completed fragment (ID, resolution, range, pixel buffer info).
+/// - `FragmentResult`: Metadata about the completed fragment (ID, resolution, range, pixel
+///   buffer info, and the iteration limit raised by adaptive iterations).
 /// - `Vec<PixelIntensity>`: The actual computed pixel data.
 ///
+/// # Errors
+/// Returns [`Cancelled`] when `control` stops the computation.
+///
 /// # Panics
 /// This function will panic if building the `FragmentResult` fails,
 /// which should only happen if required fields are missing from the task.
-pub fn process_task(task: &FragmentTask, _data_id: &[u8]) -> (FragmentResult, Vec<PixelIntensity>) {
+pub fn process_task(
+    task: &FragmentTask,
+    _data_id: &[u8],
+    threads: usize,
+    control: &GenerationControl,
+) -> Result<(FragmentResult, Vec<PixelIntensity>), Cancelled> {
     // Dynamically choose the correct fractal implementation based on the task descriptor
     let fractal = dispatch_fractal(&task.fractal);
 
-    // Compute the pixel data
-    let pixels = fractal.generate(task, &task.fractal);
+    // Compute the pixel data, split into row bands across the worker threads
+    let mut limited_task = task.clone();
+    let mut pixels = if task.options.adaptive_iterations {
+        let (pixels, max_iteration) = generate_adaptive(fractal.as_ref(), task, &task.fractal, threads, control)?;
+        limited_task.max_iteration = max_iteration;
+        pixels
+    } else {
+        generate_parallel(fractal.as_ref(), task, &task.fractal, threads, control)?
+    };
 
+    // Supersample the pixels with the iteration limit they were computed with
+    if let Some(anti_aliasing) = task.options.anti_aliasing {
+        pixels = supersample(fractal.as_ref(), &limited_task, &task.fractal, anti_aliasing, pixels, threads, control)?;
+    }
+
+    // Report the limit the counts are normalized by when it is not the one of the task
+    let mut result = fragment_result(task);
+    if task.options.adaptive_iterations {
+        println!("Task {}: adaptive iteration limit {}", task.id.offset, limited_task.max_iteration);
+        result.max_iteration = Some(limited_task.max_iteration);
+    }
+
+    Ok((result, pixels))
+}
+
+/// Tells whether the pixels of `task` can be streamed with [`stream_task`] while they are
+/// computed. Adaptive iterations and anti-aliasing refine pixels computed earlier, so
+/// they need the whole fragment at once.
+pub fn can_stream(task: &FragmentTask) -> bool {
+    !task.options.adaptive_iterations && task.options.anti_aliasing.is_none()
+}
+
+/// Computes the pixels of `task` like [`process_task`] and writes them to `sink` in row
+/// order, band by band, while the following bands are computed.
+///
+/// # Errors
+/// Returns the first error of `sink`, or an error converted from [`Cancelled`] when
+/// `control` stops the computation.
+pub fn stream_task(
+    task: &FragmentTask,
+    threads: usize,
+    control: &GenerationControl,
+    sink: &mut dyn PixelSink,
+) -> io::Result<()> {
+    let fractal = dispatch_fractal(&task.fractal);
+    stream_parallel(fractal.as_ref(), task, &task.fractal, threads, control, sink)
+}
+
+/// Builds the [`FragmentResult`] metadata of `task`.
+///
+/// # Panics
+/// This function will panic if building the `FragmentResult` fails,
+/// which should only happen if required fields are missing from the task.
+pub fn fragment_result(task: &FragmentTask) -> FragmentResult {
     let resolution = task.resolution;
     let pixel_count = resolution.nx as u32 * resolution.ny as u32;
 
-    // Build the result metadata
-    let result = FragmentResult::builder()
+    FragmentResult::builder()
         .with_id(task.id.offset, task.id.count)
         .with_resolution(resolution.nx, resolution.ny)
         .with_range(
@@ -47,7 +136,5 @@ pub fn process_task(task: &FragmentTask, _data_id: &[u8]) -> (FragmentResult, Ve
         )
         .with_pixels(task.id.offset + task.id.count, pixel_count)
         .build()
-        .expect("failed to build FragmentResult");
-
-    (result, pixels)
+        .expect("failed to build FragmentResult")
 }
@@ -4,8 +4,15 @@
 //! Each worker establishes a TCP connection to the server, requests tasks,
 //! computes the pixel data, and submits the results continuously until instructed to stop.
 
+use std::io;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use common::Precision;
+use fraktals::{CancellationToken, Cancelled, GenerationControl, ProgressCounter};
 use crate::connexion::Connection;
-use crate::processor::process_task;
+use crate::processor::{can_stream, fragment_result, process_task, resolve_precision, stream_task};
 
 /// A worker responsible for connecting to the server, receiving tasks,
 /// processing them, and returning the results.
@@ -20,6 +27,18 @@ pub struct Worker {
     pub default_port: u16,
     /// Flag used to break the run loop and gracefully terminate the worker.
     pub kill_connection: bool,
+    /// Number of threads used to compute each fragment.
+    pub threads: usize,
+    /// Arithmetic forced on every task, chosen per task when `None`.
+    pub precision: Option<Precision>,
+    /// Longest time spent on a task, after which it is abandoned for a new one.
+    pub time_budget: Option<Duration>,
+    /// Token aborting the task being computed and stopping the worker. A clone can be
+    /// cancelled from another thread.
+    pub cancellation: CancellationToken,
+    /// Rows completed and iterations spent over every task, updated while they are
+    /// computed. A clone can be read from another thread.
+    pub progress: Arc<ProgressCounter>,
 }
 
 impl Worker {
@@ -29,15 +48,47 @@ impl Worker {
     /// - `server_address`: The address of the server.
     /// - `connection_name`: A label for the worker (can be used for logging).
     /// - `default_port`: The port to connect to.
+    ///
+    /// The worker uses every available core until [`Worker::with_max_work_load`] caps it.
     pub fn new(server_address: String, connection_name: String, default_port: u16) -> Self {
         Self {
             server_address,
             connection_name,
             default_port,
             kill_connection: false,
+            threads: Self::available_threads(),
+            precision: None,
+            time_budget: None,
+            cancellation: CancellationToken::new(),
+            progress: Arc::new(ProgressCounter::new()),
         }
     }
 
+    /// Caps the number of computation threads to the workload announced in the
+    /// `FragmentRequest`, without exceeding the cores available on the machine.
+    pub fn with_max_work_load(mut self, max_work_load: u32) -> Self {
+        self.threads = (max_work_load as usize).clamp(1, Self::available_threads());
+        self
+    }
+
+    /// Forces the arithmetic of every task, overriding both the precision requested by
+    /// the tasks and the automatic selection.
+    pub fn with_precision(mut self, precision: Option<Precision>) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Abandons the tasks still running after `time_budget`, without sending their result,
+    /// and requests a new one instead.
+    pub fn with_time_budget(mut self, time_budget: Option<Duration>) -> Self {
+        self.time_budget = time_budget;
+        self
+    }
+
+    fn available_threads() -> usize {
+        thread::available_parallelism().map_or(1, |threads| threads.get())
+    }
+
     /// Starts the worker loop.
     ///
     /// The worker:
@@ -70,9 +121,32 @@ impl Worker {
                         let mut task_stream = Connection::connect(&self.server_address, self.default_port)
                             .expect("Failed to reconnect to server");
 
-                        let (result, pixels) = process_task(&task, &id);
-                        if let Err(e) = task_stream.send_result(&result, &id, &pixels) {
-                            eprintln!("Failed to send result: {}", e);
+                        resolve_precision(&mut task, self.precision);
+                        let mut control = GenerationControl::new()
+                            .with_cancellation(&self.cancellation)
+                            .with_progress(self.progress.as_ref());
+                        if let Some(time_budget) = self.time_budget {
+                            control = control.with_deadline(Instant::now() + time_budget);
+                        }
+
+                        // Stream the pixels while they are computed when no refinement pass needs them all
+                        let sent = if can_stream(&task) {
+                            let pixel_count = task.resolution.nx as usize * task.resolution.ny as usize;
+                            task_stream.send_result_streamed(&fragment_result(&task), &id, pixel_count, |sink| {
+                                stream_task(&task, self.threads, &control, sink)
+                            })
+                        } else {
+                            process_task(&task, &id, self.threads, &control)
+                                .map_err(io::Error::from)
+                                .and_then(|(result, pixels)| task_stream.send_result(&result, &id, &pixels))
+                        };
+                        if let Err(e) = sent {
+                            if Cancelled::is_cause_of(&e) {
+                                eprintln!("Task {} abandoned: {}", task.id.offset, e);
+                                self.kill_connection = self.cancellation.is_cancelled();
+                            } else {
+                                eprintln!("Failed to send result: {}", e);
+                            }
                             break;
                         }
 
@@ -99,6 +173,7 @@ impl Worker {
     /// Signals the worker to stop after the current loop iteration.
     ///
     /// This is useful for gracefully terminating the worker from the outside.
+    /// To also abort the task being computed, cancel [`Worker::cancellation`].
     pub fn stop(&mut self) {
         self.kill_connection = true;
     }